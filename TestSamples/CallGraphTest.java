@@ -0,0 +1,12 @@
+import org.graalvm.polyglot.*;
+
+public class CallGraphTest {
+    public static void main(String[] args) {
+        Context context = Context.create();
+        Value twice = context.eval("js", "(x) => x * 2");
+        twice.execute(21);
+        helper();
+    }
+
+    static void helper() {}
+}
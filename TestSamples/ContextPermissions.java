@@ -0,0 +1,14 @@
+import org.graalvm.polyglot.*;
+
+public class ContextPermissions {
+    public static void main(String[] args) {
+        Context restricted = Context.newBuilder("js").build();
+        restricted.eval("js", "Java.type('java.util.ArrayList')");
+        restricted.eval("python", "print(1)");
+        Context open = Context.newBuilder("js", "python").allowAllAccess(true).build();
+        open.eval("js", "Polyglot.eval('python', '1')");
+        try (Context context = Context.newBuilder("js", "python").allowHostClassLookup(s -> true).build()) {
+            context.eval("js", "Polyglot.evalFile('python', 'export_x.py')");
+        }
+    }
+}
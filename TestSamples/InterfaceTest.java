@@ -0,0 +1,18 @@
+import org.graalvm.polyglot.*;
+
+public class InterfaceTest {
+    interface Model {
+        double predict(double x);
+        String name();
+        default String describe() { return name(); }
+    }
+
+    public static void main(String[] args) {
+        Context context = Context.create();
+        Model model = context.eval("js", "({predict: function(x) { return x; }})").as(Model.class);
+        Value score = context.eval("python", "lambda x: x");
+        Scorer scorer = score.as(Scorer.class);
+        Ranker ranker = score.as(Ranker.class);
+        Runnable runnable = context.eval("js", "() => 1").as(Runnable.class);
+    }
+}
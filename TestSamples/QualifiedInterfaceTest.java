@@ -0,0 +1,9 @@
+import com.acme.Ranker;
+import org.graalvm.polyglot.*;
+
+public class QualifiedInterfaceTest {
+    public static void main(String[] args) {
+        Context context = Context.create();
+        Ranker ranker = context.eval("js", "({rank: function(x) { return x; }})").as(Ranker.class);
+    }
+}
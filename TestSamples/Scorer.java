@@ -0,0 +1,9 @@
+public interface Scorer {
+    double score(double x);
+    static Scorer identity() { return x -> x; }
+}
+
+interface Ranker {
+    double rank(double x);
+    int size();
+}
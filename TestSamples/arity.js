@@ -0,0 +1,8 @@
+Polyglot.evalFile("python", "call_graph.py");
+Polyglot.evalFile("python", "export_x.py");
+const square = Polyglot.import("square");
+square(1, 2);
+square(...args);
+Polyglot.eval("python", "lambda a, b=1: a")(1);
+Polyglot.eval("python", "lambda a, b=1: a")();
+Polyglot.import("x")();
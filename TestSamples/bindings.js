@@ -0,0 +1,2 @@
+Polyglot.export("x\u0041", 42);
+let y = Polyglot.import(`y`);
@@ -0,0 +1,9 @@
+import polyglot
+
+polyglot.export_value("greeting", "hello\tworld")
+
+@polyglot.export_value
+def add(a, b):
+    return a + b
+
+z = polyglot.import_value(name='z')
@@ -0,0 +1,10 @@
+import polyglot
+
+@polyglot.export_value
+def square(x):
+    return x * x
+
+def helper():
+    return square(2)
+
+polyglot.eval(language="js", string="Polyglot.import('square')(3)")
@@ -0,0 +1,15 @@
+package com.acme;
+
+public class Greeter {
+    public String prefix = "Hello ";
+
+    public Greeter() {}
+
+    public Greeter(String prefix) {
+        this.prefix = prefix;
+    }
+
+    public String greet(String name) {
+        return prefix + name;
+    }
+}
@@ -0,0 +1,6 @@
+package com.acme;
+
+public interface Named {
+    String name();
+    String label();
+}
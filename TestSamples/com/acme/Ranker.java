@@ -0,0 +1,6 @@
+package com.acme;
+
+public interface Ranker extends Named {
+    double rank(double x);
+    default String label() { return name(); }
+}
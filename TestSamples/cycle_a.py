@@ -0,0 +1,2 @@
+import polyglot
+polyglot.eval(path="cycle_b.js", language="js")
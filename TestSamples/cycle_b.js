@@ -0,0 +1,2 @@
+Polyglot.evalFile("python", "./cycle_a.py");
+Polyglot.evalFile("js", "cycle_b.js");
@@ -0,0 +1,7 @@
+const lang = "python";
+Polyglot.eval(lang, "1 + 1");
+function run(userInput) {
+    return Polyglot.eval("python", userInput);
+}
+Polyglot.evalFile("python", process.argv[2]);
+Polyglot.eval("python", "polyglot.eval(language=l, string='1')");
@@ -0,0 +1,7 @@
+const Greeter = Java.type("com.acme.Greeter");
+const ArrayList = Java.type("java.util.ArrayList");
+Polyglot.evalFile("python", "host_lookup.py");
+const greeter = new Greeter();
+greeter.greet("world");
+const list = new ArrayList();
+list.add(greeter.prefix);
@@ -0,0 +1,5 @@
+import java
+from java.util import ArrayList
+
+greeter = java.type("com.acme.Greeter")()
+greeter.greet("python")
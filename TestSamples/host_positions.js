@@ -0,0 +1,2 @@
+const x = 1;
+Polyglot.eval("python", "a = 1\nb = \"two\"\npolyglot.eval(language='js', string='let c = \\x33')");
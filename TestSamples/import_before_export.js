@@ -0,0 +1,7 @@
+let early = Polyglot.import("x");
+Polyglot.eval("python", "polyglot.export_value('x', 42)");
+let late = Polyglot.import("x");
+function later() {
+    return Polyglot.import("y");
+}
+Polyglot.export("y", later);
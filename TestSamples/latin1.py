@@ -0,0 +1,4 @@
+# -*- coding: latin-1 -*-
+import polyglot
+caf = ""
+polyglot.eval(language="js", string="let  = ''; ")
@@ -0,0 +1,3 @@
+Polyglot.evalFile("python", "model.py");
+const model = Polyglot.import("model");
+Polyglot.export("label", `model ${model.name}`);
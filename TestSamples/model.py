@@ -0,0 +1,22 @@
+import polyglot
+
+
+class Model:
+    version = 2
+
+    def __init__(self, name):
+        self.name = name
+
+    def predict(self, x, threshold=0.5):
+        return x > threshold
+
+
+@polyglot.export_value
+def train(data, *epochs):
+    return Model("trained")
+
+
+polyglot.export_value("model", Model("default"))
+polyglot.export_value("Model", Model)
+polyglot.export_value("rate", -0.1)
+polyglot.export_value("config", {"name": "model", "layers": [1, 2]})
@@ -0,0 +1,31 @@
+// Declarations of the values exported to the polyglot bindings.
+
+export interface PolyglotBindings {
+    "Model": new (name: any) => {
+        version: number;
+        name: any;
+        predict(x: any, threshold?: any): any;
+    };
+    "config": {
+        name: string;
+        layers: any;
+    };
+    "label": string;
+    "model": {
+        version: number;
+        name: any;
+        predict(x: any, threshold?: any): any;
+    };
+    "rate": number;
+    "train": (data: any, ...args: any[]) => any;
+}
+
+declare global {
+    const Polyglot: {
+        import<K extends keyof PolyglotBindings>(name: K): PolyglotBindings[K];
+        import(name: string): any;
+        export(name: string, value: any): void;
+        eval(language: string, source: string): any;
+        evalFile(language: string, path: string): any;
+    };
+}
@@ -0,0 +1,38 @@
+# Stubs for the values exported to the polyglot bindings.
+
+from typing import Any, Literal, Protocol, overload
+
+class _Model:
+    def __init__(self, name: Any) -> None: ...
+    version: float
+    name: Any
+    def predict(self, x: Any, threshold: Any = ...) -> Any: ...
+
+class _config(Protocol):
+    name: str
+    layers: Any
+
+class _model(Protocol):
+    version: float
+    name: Any
+    def predict(self, x: Any, threshold: Any = ...) -> Any: ...
+
+class _train(Protocol):
+    def __call__(self, data: Any, *args: Any) -> Any: ...
+
+@overload
+def import_value(name: Literal["Model"]) -> type[_Model]: ...
+@overload
+def import_value(name: Literal["config"]) -> _config: ...
+@overload
+def import_value(name: Literal["label"]) -> str: ...
+@overload
+def import_value(name: Literal["model"]) -> _model: ...
+@overload
+def import_value(name: Literal["rate"]) -> float: ...
+@overload
+def import_value(name: Literal["train"]) -> _train: ...
+@overload
+def import_value(name: str) -> Any: ...
+def export_value(name: str, value: Any) -> None: ...
+def eval(language: str, string: str) -> Any: ...
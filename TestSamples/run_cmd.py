@@ -0,0 +1,5 @@
+import os
+import polyglot
+
+command = polyglot.import_value("cmd")
+os.system("echo " + command)
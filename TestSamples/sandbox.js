@@ -0,0 +1,6 @@
+const cp = require("child_process");
+Polyglot.eval("python", "import subprocess; subprocess.run(['ls']); eval('1 + 1'); exec(code)");
+Polyglot.evalFile("python", "run_cmd.py");
+Polyglot.eval("python", "polyglot.eval(language='js', string=\"Java.type('java.lang.Runtime')\")");
+Polyglot.eval("python", "polyglot.eval(language='js', string=\"const exec = require('child_process').exec;\")");
+Polyglot.evalFile("python", "sandbox_aliases.py");
@@ -0,0 +1,9 @@
+from subprocess import run
+import os as o
+from os import system, popen as open_pipe
+
+run(["ls"])
+o.system("ls")
+system("ls")
+open_pipe("ls")
+print("ls")
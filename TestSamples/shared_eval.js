@@ -0,0 +1,2 @@
+Polyglot.evalFile("js", "shared_eval_a.js");
+Polyglot.evalFile("js", "shared_eval_b.js");
@@ -0,0 +1,4 @@
+import polyglot
+
+polyglot.eval(language="js", string="1 + 1")
+polyglot.eval(language="js", string=input())
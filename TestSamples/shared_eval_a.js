@@ -0,0 +1 @@
+Polyglot.evalFile("python", "shared_eval.py");
@@ -0,0 +1,8 @@
+function handle(req, res) {
+    const cmd = req.body.command;
+    Polyglot.export("cmd", cmd);
+    Polyglot.evalFile("python", "run_cmd.py");
+}
+
+const line = Polyglot.eval("python", "input()");
+require('child_process').exec(line);
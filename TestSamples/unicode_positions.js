@@ -0,0 +1 @@
+const café = "é"; Polyglot.eval("python", "naïve = '😀'; x = 1");
@@ -4,12 +4,29 @@
 pub mod util;
 
 /// The main module of the project.
-/// 
+///
 /// This module contains the PolyglotTree struct, which is the main object used to build and interact with polyglot ASTs.
 pub mod polyglot_tree;
-pub use polyglot_tree::polyglot_processor::{PolygotProcessor, TreePrinter};
+pub use polyglot_tree::polyglot_processor::arity::ArityChecker;
+pub use polyglot_tree::polyglot_processor::call_graph::CallGraphBuilder;
+pub use polyglot_tree::polyglot_processor::dynamic_eval::DynamicEvalReporter;
+pub use polyglot_tree::polyglot_processor::interfaces::InterfaceChecker;
+pub use polyglot_tree::polyglot_processor::permissions::ContextPermissionChecker;
+pub use polyglot_tree::polyglot_processor::reflection::ReflectionConfigBuilder;
+pub use polyglot_tree::polyglot_processor::sandbox::SandboxLinter;
+pub use polyglot_tree::polyglot_processor::slicing::BackwardSlicer;
+pub use polyglot_tree::polyglot_processor::stubs::StubGenerator;
+pub use polyglot_tree::polyglot_processor::taint::{TaintAnalyzer, TaintPattern};
+pub use polyglot_tree::polyglot_processor::type_inference::{TypeInferrer, ValueType};
+pub use polyglot_tree::polyglot_processor::{
+    DUBuilder, ExecutionOrderChecker, PolygotProcessor, TreePrinter,
+};
 pub use polyglot_tree::polyglot_zipper::PolyglotZipper;
-pub use polyglot_tree::PolyglotTree;
+pub use polyglot_tree::source_resolver::{
+    FileSystemResolver, InMemoryResolver, SearchPathResolver, SourceResolver,
+};
+pub use polyglot_tree::{LanguageRegion, PolyglotTree};
 
 #[cfg(test)]
+#[allow(clippy::expect_fun_call)] // assert_ast_eq formats its panic messages eagerly
 mod tests;
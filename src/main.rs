@@ -1,12 +1,37 @@
 use std::path::PathBuf;
 
-use polyglot_ast::util::Language;
+use polyglot_ast::util::{self, Language};
 use polyglot_ast::{PolyglotTree, TreePrinter};
 
+/// Prints the polyglot AST of a file, given as `<file> <language>`.
+/// Use `-` as the file to read the code from stdin, optionally followed by the name to give it;
+/// paths in the code are then relative to the current directory.
 fn main() {
-    let file = PathBuf::from("TestSamples/JavaTest.java");
-    let tree =
-        PolyglotTree::from_path(file, Language::Java).expect("Should not have parsing issues");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let tree = match args.as_slice() {
+        [] => {
+            let file = PathBuf::from("TestSamples/JavaTest.java");
+            PolyglotTree::from_path(file, Language::Java)
+        }
+        [file, language, rest @ ..] => {
+            let language = util::language_string_to_enum(language)
+                .unwrap_or_else(|_| panic!("Unsupported language {language}"));
+            if file == "-" {
+                let name = rest.first().map_or("<stdin>", |n| n.as_str());
+                let working_dir = std::env::current_dir().expect("The current directory exists");
+                PolyglotTree::from_reader(
+                    std::io::stdin(),
+                    language,
+                    PathBuf::from(name),
+                    working_dir,
+                )
+            } else {
+                PolyglotTree::from_path(PathBuf::from(file), language)
+            }
+        }
+        _ => panic!("Usage: polyglot_ast [<file> <language>], with - as the file to read stdin"),
+    };
+    let tree = tree.expect("Should not have parsing issues");
     let mut tp = TreePrinter::new();
     tree.apply(&mut tp);
     println!("{}", tp.get_result())
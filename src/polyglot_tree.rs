@@ -1,12 +1,20 @@
 use super::util;
-use super::util::Language;
+use super::util::{Language, SourceEncoding};
+pub use source_map::LanguageRegion;
+use source_map::{EmbeddedOrigin, HostSource, Transcoding};
+use source_resolver::{FileSystemResolver, SourceResolver};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tree_sitter::{Node, Parser, Tree};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use tree_sitter::{Node, Parser, Point, Tree};
 
 pub mod polyglot_processor;
 pub mod polyglot_zipper;
+mod source_map;
+pub mod source_resolver;
 
 /// An Abstract Syntax Tree (AST) spanning across multiple languages.
 ///
@@ -14,9 +22,126 @@ pub mod polyglot_zipper;
 pub struct PolyglotTree {
     tree: Tree,
     code: String,
+    file: Option<PathBuf>,
     working_dir: PathBuf,
     language: Language,
-    node_to_subtrees_map: HashMap<usize, PolyglotTree>,
+    node_to_subtrees_map: HashMap<usize, Arc<PolyglotTree>>,
+    host_classes: Option<Arc<HostClasses>>,
+    /// The names the Python `java` module is imported under, computed on first use.
+    java_modules: OnceLock<Vec<String>>,
+    origin: Option<Arc<EmbeddedOrigin>>,
+    transcoding: Option<Arc<Transcoding>>,
+    /// The code and line table of the file, built the first time a string literal of the tree is evaluated if the tree was read from a file.
+    host_source: OnceLock<Arc<HostSource>>,
+    /// The keys of the files being evaluated down to this tree, from the outermost one, used to detect cycles.
+    eval_chain: Vec<PathBuf>,
+    cyclic_references: HashMap<usize, PathBuf>,
+}
+
+/// The result of building the subtree of an eval call.
+enum Subtree {
+    Tree(Arc<PolyglotTree>),
+    /// The call evaluates a file that is already being evaluated, given by its key as found in the eval chain.
+    Cycle(PathBuf),
+}
+
+/// The state shared by the trees built by a single call to a constructor.
+struct BuildSession {
+    resolver: Arc<dyn SourceResolver>,
+    /// The trees of the files evaluated so far, by key and language, so that each file is only read and built once.
+    file_trees: Mutex<HashMap<(PathBuf, Language), Arc<PolyglotTree>>>,
+    /// The number of threads that may still be started to build subtrees, besides those already running.
+    spare_threads: AtomicUsize,
+    /// The Java classes of the project, shared by every tree of the session.
+    host_classes: Option<Arc<HostClasses>>,
+}
+
+impl BuildSession {
+    /// Creates a session building trees on at most `threads` threads, including the current one.
+    ///
+    /// Host classes are looked up in the Java files the resolver lists in `project_dir`, if given.
+    fn new(
+        resolver: Arc<dyn SourceResolver>,
+        threads: usize,
+        project_dir: Option<&Path>,
+    ) -> BuildSession {
+        let host_classes = project_dir.map(|d| {
+            Arc::new(HostClasses {
+                project_dir: d.to_path_buf(),
+                resolver: resolver.clone(),
+                trees: OnceLock::new(),
+            })
+        });
+        BuildSession {
+            resolver,
+            file_trees: Mutex::new(HashMap::new()),
+            spare_threads: AtomicUsize::new(threads.saturating_sub(1)),
+            host_classes,
+        }
+    }
+
+    /// Locks the trees of the files evaluated so far.
+    ///
+    /// Trees are only inserted once fully built, so a thread panicking while holding the lock cannot leave the map inconsistent,
+    /// and a poisoned lock is recovered rather than making every later lookup fail.
+    fn file_trees(&self) -> MutexGuard<'_, HashMap<(PathBuf, Language), Arc<PolyglotTree>>> {
+        self.file_trees
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes up to `wanted` spare threads, returning how many were taken.
+    fn reserve_threads(&self, wanted: usize) -> usize {
+        match self
+            .spare_threads
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                Some(n - n.min(wanted))
+            }) {
+            Ok(n) => n.min(wanted),
+            Err(_) => 0,
+        }
+    }
+
+    /// Gives back threads taken with `reserve_threads` once they are done.
+    fn release_threads(&self, count: usize) {
+        self.spare_threads.fetch_add(count, Ordering::SeqCst);
+    }
+}
+
+/// The Java files of the project a build reads, shared by every tree of the build so that the project is only scanned once.
+///
+/// The files are only read and parsed the first time a host class is looked up from any of these trees.
+/// Their own trees do not link host classes, which prevents host classes evaluating guest code that looks them up from being linked endlessly.
+struct HostClasses {
+    project_dir: PathBuf,
+    resolver: Arc<dyn SourceResolver>,
+    trees: OnceLock<Vec<PolyglotTree>>,
+}
+
+impl HostClasses {
+    /// Returns the trees of the Java files of the project, sorted by path.
+    fn trees(&self) -> &[PolyglotTree] {
+        self.trees.get_or_init(|| {
+            let session = BuildSession::new(self.resolver.clone(), 1, None);
+            self.resolver
+                .list_files(self.project_dir.as_path(), "java")
+                .into_iter()
+                .filter_map(|path| {
+                    let (file, bytes) = self.resolver.resolve(Path::new(""), path.as_path())?;
+                    let working_dir = file.parent()?.to_path_buf();
+                    PolyglotTree::from_bytes_impl(
+                        bytes.as_slice(),
+                        Language::Java,
+                        file,
+                        working_dir,
+                        None,
+                        &session,
+                        &[],
+                    )
+                })
+                .collect()
+        })
+    }
 }
 
 impl PolyglotTree {
@@ -31,7 +156,7 @@ impl PolyglotTree {
     /// # Arguments
     ///
     /// - `code` The code snippet to build the AST from, provided as any object that can be converted to a string.
-    /// For proper use, ensure that `code.to_string()` would provide a syntactically correct code snippet.
+    ///   For proper use, ensure that `code.to_string()` would provide a syntactically correct code snippet.
     /// - `language` The Language variant that `code` is written in.
     ///
     /// # Examples
@@ -65,14 +190,25 @@ impl PolyglotTree {
         let mut result = PolyglotTree {
             tree,
             code,
+            file: None,
             working_dir: PathBuf::new(),
             language,
             node_to_subtrees_map: HashMap::new(),
+            host_classes: None,
+            java_modules: OnceLock::new(),
+            host_source: OnceLock::new(),
+            origin: None,
+            transcoding: None,
+            eval_chain: Vec::new(),
+            cyclic_references: HashMap::new(),
         };
 
         let mut map = HashMap::new();
-        result.build_polyglot_tree(&mut map); // traverse the tree to build the subtrees
+        let mut cycles = HashMap::new();
+        let session = BuildSession::new(Arc::new(FileSystemResolver), 1, None);
+        result.build_polyglot_tree(&mut map, &mut cycles, &session); // traverse the tree to build the subtrees
         result.node_to_subtrees_map = map; // set the map after its built
+        result.cyclic_references = cycles;
         Some(result)
     }
 
@@ -87,6 +223,10 @@ impl PolyglotTree {
     ///
     /// If there is an error while reading the file, this method will "soft fail" and return None while printing a message to io::stderr.
     ///
+    /// The encoding of the file is detected with `util::detect_source_encoding`; use `from_path_with_encoding` to give it explicitly.
+    /// Files that are not UTF-8 are decoded before being parsed, so the positions of their nodes are relative to the decoded code,
+    /// but `PolyglotZipper::host_byte_range` and `PolyglotZipper::host_position` still map them to the bytes of the file.
+    ///
     /// # Arguments
     ///
     /// - `path` A PathBuf to the file containing the code.
@@ -113,9 +253,117 @@ impl PolyglotTree {
     /// This can only happen if tree_sitter and the grammars are of incompatible versions;
     /// either refer to the `tree_sitter::Parser::set_language()` documentation or directly contact polyglot_ast maintainers if this method keeps panicking.
     pub fn from_path(path: PathBuf, language: Language) -> Option<PolyglotTree> {
+        Self::from_path_impl(path, language, None, 1)
+    }
+
+    /// Given a path to a file, a Language and the encoding of the file, returns a PolyglotTree instance that represents the program written in the file.
+    ///
+    /// This behaves as `from_path`, but reads the file with the given encoding instead of detecting it.
+    /// Files evaluated from it with `evalFile` still have their encoding detected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::{Language, SourceEncoding};
+    ///
+    /// let file = PathBuf::from("TestSamples/latin1.py");
+    /// let tree = PolyglotTree::from_path_with_encoding(file, Language::Python, SourceEncoding::Latin1).expect("This test file exists");
+    /// ```
+    pub fn from_path_with_encoding(
+        path: PathBuf,
+        language: Language,
+        encoding: SourceEncoding,
+    ) -> Option<PolyglotTree> {
+        Self::from_path_impl(path, language, Some(encoding), 1)
+    }
+
+    /// Given a path to a file, a Language and a number of threads, returns a PolyglotTree instance that represents the program written in the file.
+    ///
+    /// This behaves as `from_path`, but the subtrees of the eval calls of each tree are built on up to `threads` threads,
+    /// including the current one, which is faster for programs evaluating many files.
+    /// There is no thread pool: a tree with several eval calls starts threads for them while fewer than `threads` are running in the whole build,
+    /// and builds the rest of its calls itself.
+    /// `std::thread::available_parallelism` gives a good number of threads for the current machine.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let file = PathBuf::from("TestSamples/dynamic_eval.js");
+    /// let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    /// let tree = PolyglotTree::from_path_parallel(file, Language::JavaScript, threads).expect("This test file exists");
+    /// ```
+    pub fn from_path_parallel(
+        path: PathBuf,
+        language: Language,
+        threads: usize,
+    ) -> Option<PolyglotTree> {
+        Self::from_path_impl(path, language, None, threads)
+    }
+
+    /// Given a path to a file, a Language and a SourceResolver, returns a PolyglotTree instance that represents the program written in the file.
+    ///
+    /// This behaves as `from_path`, but reads the file and every file evaluated from it, at any depth, through `resolver`,
+    /// so that trees can be built from sources that are not on disk.
+    /// The file is resolved with `path` relative to an empty working directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use std::sync::Arc;
+    /// use polyglot_ast::{InMemoryResolver, PolyglotTree};
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let mut resolver = InMemoryResolver::new();
+    /// resolver.insert("app/main.js", "Polyglot.evalFile('python', 'lib.py')");
+    /// resolver.insert("app/lib.py", "x = 42");
+    ///
+    /// let tree = PolyglotTree::from_path_with_resolver(PathBuf::from("app/main.js"), Language::JavaScript, Arc::new(resolver))
+    ///     .expect("The file is in the resolver");
+    /// ```
+    pub fn from_path_with_resolver(
+        path: PathBuf,
+        language: Language,
+        resolver: Arc<dyn SourceResolver>,
+    ) -> Option<PolyglotTree> {
+        let (file, bytes) = match resolver.resolve(Path::new(""), path.as_path()) {
+            Some(resolved) => resolved,
+            None => {
+                eprintln!(
+                    "Warning: unable to create tree for file {} because it could not be resolved",
+                    path.display()
+                );
+                return None;
+            }
+        };
+        let working_dir = file.parent()?.to_path_buf();
+        let session = BuildSession::new(resolver, 1, Some(working_dir.as_path()));
+        Self::from_bytes_impl(
+            bytes.as_slice(),
+            language,
+            file,
+            working_dir,
+            None,
+            &session,
+            &[],
+        )
+    }
+
+    fn from_path_impl(
+        path: PathBuf,
+        language: Language,
+        encoding: Option<SourceEncoding>,
+        threads: usize,
+    ) -> Option<PolyglotTree> {
         let file = path.clone();
-        let code = match std::fs::read_to_string(path) {
-            Ok(s) => s,
+        let bytes = match std::fs::read(path) {
+            Ok(b) => b,
             Err(e) => {
                 eprintln!(
                     "Warning: unable to create tree for file {} due to the following error: {e}",
@@ -124,6 +372,94 @@ impl PolyglotTree {
                 return None;
             }
         };
+        let working_dir = file.parent()?.to_path_buf();
+        let session = BuildSession::new(
+            Arc::new(FileSystemResolver),
+            threads,
+            Some(working_dir.as_path()),
+        );
+        Self::from_bytes_impl(
+            bytes.as_slice(),
+            language,
+            file,
+            working_dir,
+            encoding,
+            &session,
+            &[],
+        )
+    }
+
+    /// Given the contents of a source file, a Language, the name of the file and its working directory,
+    /// returns a PolyglotTree instance that represents the program written in the contents.
+    ///
+    /// This behaves as `from_path` for a file named `file` with the given contents, except that nothing is read from `file` itself:
+    /// the name is only used to identify the code, for instance in `PolyglotZipper::get_file`, and may not exist.
+    /// Files evaluated with `evalFile` and host classes are looked up from `working_dir`.
+    ///
+    /// # Arguments
+    ///
+    /// - `bytes` The contents of the file, whose encoding is detected with `util::detect_source_encoding`.
+    /// - `language` The Language variant that the contents are written in.
+    /// - `file` The name to give the file, such as `<stdin>` or the path it would be written to.
+    /// - `working_dir` The directory paths in the code are relative to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let code = b"import polyglot\npolyglot.eval(path=\"export_x.py\", language=\"python\")";
+    /// let tree = PolyglotTree::from_bytes(code, Language::Python, PathBuf::from("<generated>"), PathBuf::from("TestSamples"))
+    ///     .expect("Python is a supported language");
+    /// ```
+    pub fn from_bytes(
+        bytes: &[u8],
+        language: Language,
+        file: PathBuf,
+        working_dir: PathBuf,
+    ) -> Option<PolyglotTree> {
+        let session =
+            BuildSession::new(Arc::new(FileSystemResolver), 1, Some(working_dir.as_path()));
+        Self::from_bytes_impl(bytes, language, file, working_dir, None, &session, &[])
+    }
+
+    /// Given a reader over the contents of a source file, a Language, the name of the file and its working directory,
+    /// returns a PolyglotTree instance that represents the program written in the contents.
+    ///
+    /// This reads `reader` to its end, then behaves as `from_bytes`; for instance, `std::io::stdin()` can be passed to analyse piped code.
+    /// If there is an error while reading, this method will "soft fail" and return None while printing a message to io::stderr.
+    pub fn from_reader(
+        mut reader: impl std::io::Read,
+        language: Language,
+        file: PathBuf,
+        working_dir: PathBuf,
+    ) -> Option<PolyglotTree> {
+        let mut bytes = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut bytes) {
+            eprintln!(
+                "Warning: unable to create tree for file {} due to the following error: {e}",
+                file.display()
+            );
+            return None;
+        }
+        Self::from_bytes(bytes.as_slice(), language, file, working_dir)
+    }
+
+    fn from_bytes_impl(
+        bytes: &[u8],
+        language: Language,
+        file: PathBuf,
+        working_dir: PathBuf,
+        encoding: Option<SourceEncoding>,
+        session: &BuildSession,
+        ancestors: &[PathBuf],
+    ) -> Option<PolyglotTree> {
+        let mut eval_chain = ancestors.to_vec();
+        eval_chain.push(session.resolver.file_key(file.as_path()));
+        let encoding = encoding.unwrap_or_else(|| util::detect_source_encoding(bytes, &language));
+        let (code, transcoding) = Transcoding::decode(bytes, encoding);
 
         let mut parser = Parser::new();
         let ts_lang = util::language_enum_to_treesitter(&language);
@@ -137,14 +473,24 @@ impl PolyglotTree {
         let mut result = PolyglotTree {
             tree,
             code,
-            working_dir: file.parent()?.to_path_buf(),
+            working_dir,
+            file: Some(file),
             language,
             node_to_subtrees_map: HashMap::new(),
+            host_classes: session.host_classes.clone(),
+            java_modules: OnceLock::new(),
+            host_source: OnceLock::new(),
+            origin: None,
+            transcoding: transcoding.map(Arc::new),
+            eval_chain,
+            cyclic_references: HashMap::new(),
         };
 
         let mut map = HashMap::new();
-        result.build_polyglot_tree(&mut map);
+        let mut cycles = HashMap::new();
+        result.build_polyglot_tree(&mut map, &mut cycles, session);
         result.node_to_subtrees_map = map;
+        result.cyclic_references = cycles;
         Some(result)
     }
 
@@ -154,9 +500,13 @@ impl PolyglotTree {
     /// # Arguments
     ///
     /// - `code` The code snippet to build the AST from, provided as any object that can be converted to a string.
-    /// For proper use, ensure that `code.to_string()` would provide a syntactically correct code snippet.
+    ///   For proper use, ensure that `code.to_string()` would provide a syntactically correct code snippet.
     /// - `language` The Language variant that the file at `path` is written in.
     /// - `working_dir` a PathBuf of the parent directory of the file currently being processed.
+    /// - `file` the file in which `code` physically appears, if any.
+    /// - `origin` where `code` appears in `file`, if it was decoded from a string literal.
+    /// - `session` the build session of the outermost tree, to read and share the files evaluated by `code` with.
+    /// - `eval_chain` the keys of the files being evaluated down to `code`.
     ///
     /// # Panics
     ///
@@ -168,6 +518,10 @@ impl PolyglotTree {
         code: impl ToString,
         language: Language,
         working_dir: PathBuf,
+        file: Option<PathBuf>,
+        origin: Option<Arc<EmbeddedOrigin>>,
+        session: &BuildSession,
+        eval_chain: Vec<PathBuf>,
     ) -> Option<PolyglotTree> {
         let code = code.to_string();
 
@@ -183,14 +537,24 @@ impl PolyglotTree {
         let mut result = PolyglotTree {
             tree,
             code,
+            file,
             working_dir,
             language,
             node_to_subtrees_map: HashMap::new(),
+            host_classes: session.host_classes.clone(),
+            java_modules: OnceLock::new(),
+            host_source: OnceLock::new(),
+            origin,
+            transcoding: None,
+            eval_chain,
+            cyclic_references: HashMap::new(),
         };
 
         let mut map = HashMap::new();
-        result.build_polyglot_tree(&mut map);
+        let mut cycles = HashMap::new();
+        result.build_polyglot_tree(&mut map, &mut cycles, session);
         result.node_to_subtrees_map = map;
+        result.cyclic_references = cycles;
         Some(result)
     }
 
@@ -200,45 +564,373 @@ impl PolyglotTree {
         processor.process(polyglot_zipper::PolyglotZipper::from(self))
     }
 
+    /// Returns the innermost node at the given position of a file, along with the languages crossed to reach it.
+    ///
+    /// `path` is the file the position is in, which is either the file the tree was built from or one evaluated from it with `evalFile`.
+    /// When the position falls inside code evaluated from a string literal, the lookup descends into the tree built from it, at any depth.
+    /// The languages start with the language of the file and end with the language of the returned node.
+    ///
+    /// Returns None if no part of the tree was read from `path`, or if the position is past the end of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    /// use tree_sitter::Point;
+    ///
+    /// let file = PathBuf::from("TestSamples/host_positions.js");
+    /// let tree = PolyglotTree::from_path(file, Language::JavaScript).expect("This test file exists");
+    ///
+    /// let (node, languages) = tree.node_at(Path::new("TestSamples/host_positions.js"), Point::new(1, 32)).expect("The position is in the file");
+    /// assert_eq!(node.code(), "b");
+    /// assert_eq!(languages, [Language::JavaScript, Language::Python]);
+    /// ```
+    pub fn node_at(
+        &self,
+        path: &Path,
+        position: Point,
+    ) -> Option<(polyglot_zipper::PolyglotZipper<'_>, Vec<Language>)> {
+        let tree = self.file_tree(path)?;
+        let line_start = match position.row {
+            0 => 0,
+            row => tree.code.match_indices('\n').nth(row - 1)?.0 + 1,
+        };
+        let line_end = tree.code[line_start..]
+            .find('\n')
+            .map_or(tree.code.len(), |i| line_start + i);
+        // columns count the bytes of the file, which differ from those of the code if it was not UTF-8
+        let byte = match tree.transcoding.as_deref() {
+            Some(t) => t.to_decoded(t.to_original(line_start) + position.column),
+            None => line_start + position.column,
+        };
+        tree.node_at_byte_impl(byte.min(line_end))
+    }
+
+    /// Returns the innermost node at the given byte offset of a file, along with the languages crossed to reach it.
+    ///
+    /// This behaves as `node_at`, with the position given as a byte offset in the file.
+    pub fn node_at_byte(
+        &self,
+        path: &Path,
+        byte: usize,
+    ) -> Option<(polyglot_zipper::PolyglotZipper<'_>, Vec<Language>)> {
+        let tree = self.file_tree(path)?;
+        let byte = match tree.transcoding.as_deref() {
+            Some(transcoding) => transcoding.to_decoded(byte),
+            None => byte,
+        };
+        if byte > tree.code.len() {
+            return None;
+        }
+        tree.node_at_byte_impl(byte)
+    }
+
+    /// Returns the regions of a file by language, for instance to highlight each of them with the right grammar.
+    ///
+    /// `path` is the file to split, which is either the file the tree was built from or one evaluated from it with `evalFile`.
+    /// The regions do not overlap and cover the whole file in order: the contents of each string literal evaluated as code,
+    /// at any depth, form regions of the evaluated language, and the code around them regions of the enclosing language.
+    ///
+    /// Returns an empty list if no part of the tree was read from `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use polyglot_ast::PolyglotTree;
+    /// use polyglot_ast::util::Language;
+    ///
+    /// let tree = PolyglotTree::from_path(Path::new("TestSamples/host_positions.js").to_path_buf(), Language::JavaScript).expect("This test file exists");
+    /// let regions = tree.language_regions(Path::new("TestSamples/host_positions.js"));
+    ///
+    /// let languages: Vec<_> = regions.iter().map(|r| (r.get_lang(), r.get_depth())).collect();
+    /// assert_eq!(languages[..3], [(Language::JavaScript, 0), (Language::Python, 1), (Language::JavaScript, 2)]);
+    /// ```
+    pub fn language_regions(&self, path: &Path) -> Vec<LanguageRegion> {
+        let mut regions = Vec::new();
+        if let Some(tree) = self.file_tree(path) {
+            tree.language_regions_impl(0..tree.code.len(), 0, &mut regions);
+            if let Some(transcoding) = tree.transcoding.as_deref() {
+                for region in regions.iter_mut() {
+                    let range = region.byte_range();
+                    *region = LanguageRegion::new(
+                        transcoding.to_original(range.start)..transcoding.to_original(range.end),
+                        region.get_lang(),
+                        region.get_depth(),
+                    );
+                }
+            }
+        }
+        regions
+    }
+
+    fn language_regions_impl(
+        &self,
+        range: std::ops::Range<usize>,
+        depth: usize,
+        regions: &mut Vec<LanguageRegion>,
+    ) {
+        // code evaluated from a file is not part of this one, and is skipped
+        let mut embedded: Vec<_> = self
+            .node_to_subtrees_map
+            .values()
+            .filter(|t| t.origin.is_some())
+            .map(|t| (t.host_text_byte(0)..t.host_text_byte(t.code.len()), t))
+            .collect();
+        embedded.sort_by_key(|(r, _)| r.start);
+
+        let mut start = range.start;
+        for (embedded_range, tree) in embedded {
+            if start < embedded_range.start {
+                regions.push(LanguageRegion::new(
+                    start..embedded_range.start,
+                    self.language,
+                    depth,
+                ));
+            }
+            start = embedded_range.end;
+            tree.language_regions_impl(embedded_range, depth + 1, regions);
+        }
+        if start < range.end {
+            regions.push(LanguageRegion::new(start..range.end, self.language, depth));
+        }
+    }
+
+    /// Internal function to find the tree read from the given file, which is either this tree or one evaluated from it with `evalFile`.
+    fn file_tree(&self, path: &Path) -> Option<&PolyglotTree> {
+        let is_file = self.origin.is_none()
+            && self.file.as_deref().is_some_and(|f| {
+                f == path
+                    || matches!((f.canonicalize(), path.canonicalize()), (Ok(a), Ok(b)) if a == b)
+            });
+        if is_file {
+            return Some(self);
+        }
+        self.node_to_subtrees_map
+            .values()
+            .find_map(|t| t.file_tree(path))
+    }
+
+    fn node_at_byte_impl(
+        &self,
+        byte: usize,
+    ) -> Option<(polyglot_zipper::PolyglotZipper<'_>, Vec<Language>)> {
+        let contains = |tree: &PolyglotTree, node: Node| {
+            tree.host_text_byte(node.start_byte()) <= byte
+                && byte < tree.host_text_byte(node.end_byte())
+        };
+
+        let mut tree = self;
+        let mut node = self.root_node();
+        let mut languages = vec![self.language];
+        'descend: loop {
+            // code evaluated from a file is not part of this one, and is skipped
+            let subtree = tree
+                .node_to_subtrees_map
+                .get(&node.id())
+                .filter(|t| t.origin.is_some() && contains(t, t.root_node()));
+            if let Some(subtree) = subtree {
+                tree = subtree;
+                node = subtree.root_node();
+                languages.push(subtree.language);
+                continue;
+            }
+
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                if contains(tree, child) {
+                    node = child;
+                    continue 'descend;
+                }
+            }
+            break;
+        }
+        Some((
+            polyglot_zipper::PolyglotZipper::from_impl(tree, node),
+            languages,
+        ))
+    }
+
     /// Internal function to get a node's source code.
     fn node_to_code(&self, node: Node) -> &str {
         &self.code[node.start_byte()..node.end_byte()]
     }
 
+    /// Internal function to get the trees of the Java files of the project the tree was built in,
+    /// or none if the tree was built from a code snippet or is itself one of these files.
+    pub(crate) fn host_class_trees(&self) -> &[PolyglotTree] {
+        match &self.host_classes {
+            Some(host_classes) => host_classes.trees(),
+            None => &[],
+        }
+    }
+
+    /// Internal function to get the tree of the Java file declaring the given host class, if it is part of the project.
+    fn host_class_tree(&self, class_name: &str) -> Option<&PolyglotTree> {
+        self.host_class_trees()
+            .iter()
+            .find(|t| t.declares_type(class_name))
+    }
+
+    /// Returns true if this Java tree declares the class, interface or enum with the given fully qualified name.
+    pub(crate) fn declares_type(&self, qualified_name: &str) -> bool {
+        let root = self.root_node();
+        let mut cursor = root.walk();
+        let package = root
+            .named_children(&mut cursor)
+            .find(|c| c.kind().eq("package_declaration"))
+            .and_then(|p| p.named_child(0))
+            .map(|p| self.node_to_code(p));
+        let nested_name = match package {
+            Some(package) => match qualified_name.strip_prefix(package) {
+                Some(rest) if rest.starts_with('.') => &rest[1..],
+                _ => return false,
+            },
+            None => qualified_name,
+        };
+        // nested classes may be written `Outer.Inner` or `Outer$Inner`
+        let simple_name = nested_name.rsplit(['.', '$']).next().unwrap_or(nested_name);
+        self.find_type_declaration(root, simple_name).is_some()
+    }
+
+    /// Internal function to find the declaration of the class, interface or enum with the given simple name.
+    fn find_type_declaration<'a>(&'a self, node: Node<'a>, simple_name: &str) -> Option<Node<'a>> {
+        if matches!(
+            node.kind(),
+            "class_declaration" | "interface_declaration" | "enum_declaration"
+        ) {
+            let mut cursor = node.walk();
+            let name = node
+                .named_children(&mut cursor)
+                .find(|c| c.kind().eq("identifier"));
+            if name.is_some_and(|n| self.node_to_code(n).eq(simple_name)) {
+                return Some(node);
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node<'a>> = node.named_children(&mut cursor).collect();
+        children
+            .into_iter()
+            .find_map(|c| self.find_type_declaration(c, simple_name))
+    }
+
     /// Internal function to get the root node of the tree.
-    fn root_node(&self) -> Node {
+    fn root_node(&self) -> Node<'_> {
         self.tree.root_node()
     }
 
     /// Internal function to start building the polyglot mappings and subtrees.
-    fn build_polyglot_tree(&self, node_tree_map: &mut HashMap<usize, PolyglotTree>) {
-        let root = self.tree.root_node();
-        self.build_polyglot_links(node_tree_map, root); // we get the root, and then call the recursive function
+    ///
+    /// The eval calls are collected first, then their subtrees are built on as many spare threads of the session as useful.
+    fn build_polyglot_tree(
+        &self,
+        node_tree_map: &mut HashMap<usize, Arc<PolyglotTree>>,
+        cyclic_references: &mut HashMap<usize, PathBuf>,
+        session: &BuildSession,
+    ) {
+        let mut calls = Vec::new();
+        // we get the root, and then call the recursive function
+        self.collect_eval_calls(self.tree.root_node(), &mut calls);
+
+        let extra_threads = session.reserve_threads(calls.len().saturating_sub(1));
+        if extra_threads == 0 {
+            for node in calls {
+                self.build_subtree(node_tree_map, cyclic_references, session, node);
+            }
+            return;
+        }
+
+        // tree-sitter nodes cannot be sent to other threads, and trees must be copied to be used on several threads,
+        // so each thread finds its nodes again from their range and id in its own copy, which shares the ids of the original
+        let sites: Vec<(Range<usize>, usize)> = calls
+            .iter()
+            .map(|n| (n.start_byte()..n.end_byte(), n.id()))
+            .collect();
+        let chunk_size = sites.len().div_ceil(extra_threads + 1);
+        let build_chunk = |chunk: &[(Range<usize>, usize)], tree: Tree| {
+            let mut map = HashMap::new();
+            let mut cycles = HashMap::new();
+            for (range, id) in chunk {
+                if let Some(node) = Self::node_with_id(&tree, range.clone(), *id) {
+                    self.build_subtree(&mut map, &mut cycles, session, node);
+                }
+            }
+            (map, cycles)
+        };
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let mut chunks = sites.chunks(chunk_size);
+            let first = chunks.next().unwrap_or_default();
+            let handles: Vec<_> = chunks
+                .map(|chunk| {
+                    let tree = self.tree.clone();
+                    scope.spawn(move || build_chunk(chunk, tree))
+                })
+                .collect();
+            let mut results = vec![build_chunk(first, self.tree.clone())];
+            for handle in handles {
+                results.push(
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e)),
+                );
+            }
+            results
+        });
+        session.release_threads(extra_threads);
+
+        for (map, cycles) in results {
+            node_tree_map.extend(map);
+            cyclic_references.extend(cycles);
+        }
     }
 
-    /// Internal recursive function that iterates over the nodes in the tree, and builds all subtrees as well as the polyglot link map.
-    fn build_polyglot_links(&self, node_tree_map: &mut HashMap<usize, PolyglotTree>, node: Node) {
+    /// Internal recursive function that iterates over the nodes in the tree, and collects the polyglot eval calls.
+    fn collect_eval_calls<'t>(&self, node: Node<'t>, calls: &mut Vec<Node<'t>>) {
         if self.is_polyglot_eval_call(node) {
-            if !self.make_subtree(node_tree_map, node) {
-                // If building the subtree failed,
-                // we want to soft fail (eg. not panic) to avoid interrupting the tree building.
-                // Eventually, this should be made into a proper Error,
-                // but for now for debugging purposes it just prints a warning.
-                eprintln!(
-                    "Warning: unable to make subtree for polyglot call at position {}",
-                    node.start_position()
-                )
-            }
+            calls.push(node);
         } else {
             if let Some(child) = node.child(0) {
-                self.build_polyglot_links(node_tree_map, child)
+                self.collect_eval_calls(child, calls)
             };
             if let Some(sibling) = node.next_sibling() {
-                self.build_polyglot_links(node_tree_map, sibling)
+                self.collect_eval_calls(sibling, calls)
             };
         }
     }
 
+    /// Internal function to find the node with the given id, which spans the given byte range, in a copy of the tree.
+    fn node_with_id(tree: &Tree, range: Range<usize>, id: usize) -> Option<Node<'_>> {
+        let mut node = tree
+            .root_node()
+            .descendant_for_byte_range(range.start, range.end)?;
+        while node.id() != id {
+            node = node.parent()?;
+        }
+        Some(node)
+    }
+
+    /// Internal function to build the subtree of an eval call and add it to the polyglot link map.
+    fn build_subtree(
+        &self,
+        node_tree_map: &mut HashMap<usize, Arc<PolyglotTree>>,
+        cyclic_references: &mut HashMap<usize, PathBuf>,
+        session: &BuildSession,
+        node: Node,
+    ) {
+        if !self.make_subtree(node_tree_map, cyclic_references, session, node) {
+            // If building the subtree failed,
+            // we want to soft fail (eg. not panic) to avoid interrupting the tree building.
+            // Eventually, this should be made into a proper Error,
+            // but for now for debugging purposes it just prints a warning.
+            eprintln!(
+                "Warning: unable to make subtree for polyglot call at position {}",
+                node.start_position()
+            )
+        }
+    }
+
     fn get_polyglot_call_python(&self, node: Node) -> Option<&str> {
         let child = node.child(0)?;
         if node.kind().eq("call") && child.kind().eq("attribute") {
@@ -291,10 +983,12 @@ impl PolyglotTree {
 
     fn is_polyglot_export_call(&self, node: Node) -> bool {
         match self.language {
-            Language::Python => matches!(
-                self.get_polyglot_call_python(node),
-                Some("polyglot.export_value")
-            ),
+            Language::Python => {
+                matches!(
+                    self.get_polyglot_call_python(node),
+                    Some("polyglot.export_value")
+                ) || self.is_polyglot_export_decorator(node)
+            }
             Language::JavaScript => {
                 matches!(self.get_polyglot_call_js(node), Some("Polyglot.export"))
             }
@@ -302,17 +996,365 @@ impl PolyglotTree {
         }
     }
 
-    fn make_subtree(&self, node_tree_map: &mut HashMap<usize, PolyglotTree>, node: Node) -> bool {
-        let subtree: PolyglotTree;
-        let result: Option<PolyglotTree> = match self.language {
+    fn is_host_type_lookup(&self, node: Node) -> bool {
+        self.host_type_name(node).is_some()
+    }
+
+    /// Internal function to get the fully qualified name of the host class a node looks up, if it is a host type lookup.
+    ///
+    /// These are calls to `Java.type` in JavaScript. In Python, they are calls to `java.type`, imports from the `java` module
+    /// such as `from java.util import ArrayList` or `import java.util.ArrayList as List`,
+    /// and uses of classes through the imported module itself, such as `java.util.ArrayList` after `import java`.
+    fn host_type_name(&self, node: Node) -> Option<String> {
+        match self.language {
+            Language::Python => match node.kind() {
+                "call" => {
+                    let function = self.get_polyglot_call_python(node)?;
+                    let module = function.strip_suffix(".type")?;
+                    if module != "java" && !self.java_modules().iter().any(|m| m == module) {
+                        return None;
+                    }
+                    self.string_literal_value(
+                        node.child_by_field_name("arguments")?.named_child(0)?,
+                    )
+                }
+                "dotted_name" => {
+                    let mut parent = node.parent()?;
+                    if parent.kind() == "aliased_import" {
+                        parent = parent.parent()?;
+                    }
+                    match parent.kind() {
+                        "import_statement" => {
+                            let name = self.node_to_code(node);
+                            name.starts_with("java.").then(|| String::from(name))
+                        }
+                        "import_from_statement"
+                            if parent.child_by_field_name("module_name") != Some(node) =>
+                        {
+                            let module =
+                                self.node_to_code(parent.child_by_field_name("module_name")?);
+                            (module == "java" || module.starts_with("java."))
+                                .then(|| format!("{module}.{}", self.node_to_code(node)))
+                        }
+                        _ => None,
+                    }
+                }
+                "attribute" => {
+                    // Only the attribute naming the class is a lookup, such as `java.util.Map` in `java.util.Map.Entry`
+                    let name: String = self
+                        .node_to_code(node)
+                        .split('.')
+                        .map(str::trim)
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    let (package, class) = name.rsplit_once('.')?;
+                    let mut segments = package.split('.');
+                    let module = segments.next()?;
+                    let starts_upper = |s: &str| s.starts_with(|c: char| c.is_ascii_uppercase());
+                    if !starts_upper(class)
+                        || segments.any(starts_upper)
+                        || !self.java_modules().iter().any(|m| m == module)
+                    {
+                        return None;
+                    }
+                    Some(format!("java.{}", name.split_once('.')?.1))
+                }
+                _ => None,
+            },
+            Language::JavaScript => {
+                if !self.get_polyglot_call_js(node)?.eq("Java.type") {
+                    return None;
+                }
+                self.string_literal_value(node.child_by_field_name("arguments")?.named_child(0)?)
+            }
+            Language::Java => None,
+        }
+    }
+
+    /// Internal function to get the names the Python `java` module is imported under, with `import java` or `import java as j`.
+    fn java_modules(&self) -> &[String] {
+        self.java_modules.get_or_init(|| {
+            if self.language != Language::Python {
+                return Vec::new();
+            }
+            let mut modules = Vec::new();
+            let mut stack = vec![self.tree.root_node()];
+            while let Some(node) = stack.pop() {
+                if node.kind() == "import_statement" {
+                    let mut cursor = node.walk();
+                    for name in node.children_by_field_name("name", &mut cursor) {
+                        match name.kind() {
+                            "dotted_name" if self.node_to_code(name) == "java" => {
+                                modules.push(String::from("java"));
+                            }
+                            "aliased_import"
+                                if name
+                                    .child_by_field_name("name")
+                                    .is_some_and(|n| self.node_to_code(n) == "java") =>
+                            {
+                                if let Some(alias) = name.child_by_field_name("alias") {
+                                    modules.push(String::from(self.node_to_code(alias)));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                } else {
+                    let mut cursor = node.walk();
+                    stack.extend(node.named_children(&mut cursor));
+                }
+            }
+            modules
+        })
+    }
+
+    fn is_function_definition(&self, node: Node) -> bool {
+        match self.language {
+            Language::Python => matches!(node.kind(), "function_definition" | "lambda"),
+            Language::JavaScript => matches!(
+                node.kind(),
+                "function_declaration"
+                    | "function"
+                    | "function_expression"
+                    | "arrow_function"
+                    | "method_definition"
+                    | "generator_function_declaration"
+                    | "generator_function"
+            ),
+            Language::Java => matches!(
+                node.kind(),
+                "method_declaration" | "constructor_declaration" | "lambda_expression"
+            ),
+        }
+    }
+
+    /// GraalPy allows exporting a function under its own name by decorating it with `@polyglot.export_value`.
+    fn is_polyglot_export_decorator(&self, node: Node) -> bool {
+        node.kind().eq("decorator")
+            && matches!(
+                node.named_child(0).map(|n| self.node_to_code(n)),
+                Some("polyglot.export_value")
+            )
+    }
+
+    /// Internal function to get the value of a string literal node, with its escape sequences decoded.
+    ///
+    /// Returns None if the node is not a string literal, or if it contains interpolations and thus has no static value.
+    fn string_literal_value(&self, node: Node) -> Option<String> {
+        if !matches!(node.kind(), "string" | "template_string" | "string_literal") {
+            return None;
+        }
+        let mut cursor = node.walk();
+        let mut children = node.named_children(&mut cursor);
+        if children.any(|c| matches!(c.kind(), "interpolation" | "template_substitution")) {
+            return None;
+        }
+        Some(util::unescape_string_literal(self.node_to_code(node)))
+    }
+
+    /// Internal function to build the subtree of a file evaluated by a polyglot call, resolving `path` from the working directory.
+    ///
+    /// If the file is already being evaluated higher up the tree, it is not built again, which would never end.
+    /// Otherwise, each file is only read and built once per session for each language it is evaluated as, and its tree is shared.
+    /// Files are identified by the key the resolver gives them, such as their canonical path on disk.
+    fn file_subtree(
+        &self,
+        path: PathBuf,
+        language: Language,
+        session: &BuildSession,
+    ) -> Option<Subtree> {
+        // the cache only holds files that were resolved, and resolvers look in the working directory first,
+        // so a file found there is the one the resolver would read
+        let requested = session
+            .resolver
+            .file_key(self.working_dir.join(path.as_path()).as_path());
+        if let Some(subtree) = self.known_file_subtree(requested.as_path(), language, session) {
+            return Some(subtree);
+        }
+
+        let (file, bytes) = match session
+            .resolver
+            .resolve(self.working_dir.as_path(), path.as_path())
+        {
+            Some(resolved) => resolved,
+            None => {
+                eprintln!(
+                    "Warning: unable to create tree for file {} because it could not be resolved from {}",
+                    path.display(),
+                    self.working_dir.display()
+                );
+                return None;
+            }
+        };
+        let key = session.resolver.file_key(file.as_path());
+        if let Some(subtree) = self.known_file_subtree(key.as_path(), language, session) {
+            return Some(subtree);
+        }
+
+        let working_dir = file.parent()?.to_path_buf();
+        let subtree = Arc::new(Self::from_bytes_impl(
+            bytes.as_slice(),
+            language,
+            file,
+            working_dir,
+            None,
+            session,
+            self.eval_chain.as_slice(),
+        )?);
+        // trees with cyclic references depend on the files evaluating them, so they cannot be shared
+        if subtree.contains_cycles() {
+            return Some(Subtree::Tree(subtree));
+        }
+        // another thread may have built the same file meanwhile, in which case its tree is kept
+        let shared = session
+            .file_trees()
+            .entry((key, language))
+            .or_insert(subtree)
+            .clone();
+        Some(Subtree::Tree(shared))
+    }
+
+    /// Internal function to get the subtree of the file with the given key without reading it,
+    /// if it is already being evaluated higher up the tree or was already built in the session.
+    fn known_file_subtree(
+        &self,
+        key: &Path,
+        language: Language,
+        session: &BuildSession,
+    ) -> Option<Subtree> {
+        if let Some(ancestor) = self.eval_chain.iter().find(|p| *p == key) {
+            return Some(Subtree::Cycle(ancestor.clone()));
+        }
+        let file_trees = session.file_trees();
+        let tree = file_trees.get(&(key.to_path_buf(), language))?;
+        Some(Subtree::Tree(tree.clone()))
+    }
+
+    /// Internal function to check whether this tree or any of its subtrees has a cyclic eval reference.
+    fn contains_cycles(&self) -> bool {
+        !self.cyclic_references.is_empty()
+            || self
+                .node_to_subtrees_map
+                .values()
+                .any(|t| t.contains_cycles())
+    }
+
+    /// Internal function to decode the code passed to an eval call as a string literal, along with where it appears in the host file.
+    fn embedded_code(&self, literal: Node) -> (String, Arc<EmbeddedOrigin>) {
+        let (code, segments) = util::unescape_string_literal_mapped(self.node_to_code(literal));
+        let origin = EmbeddedOrigin::new(
+            segments,
+            literal.start_byte(),
+            self.origin.clone(),
+            self.host_source(),
+        );
+        (code, Arc::new(origin))
+    }
+
+    /// Internal function to get the code and line table of the outermost file the code of this tree physically appears in.
+    fn host_source(&self) -> Arc<HostSource> {
+        match &self.origin {
+            Some(origin) => origin.host().clone(),
+            None => self
+                .host_source
+                .get_or_init(|| Arc::new(HostSource::new(&self.code, self.transcoding.clone())))
+                .clone(),
+        }
+    }
+
+    /// Internal function to know whether the code of this tree was decoded from a string literal of another tree.
+    pub(crate) fn is_embedded(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    /// Internal function to map a byte offset in the code of this tree to the decoded code of the outermost file it physically appears in.
+    fn host_text_byte(&self, byte: usize) -> usize {
+        match &self.origin {
+            Some(origin) => origin.host_byte(byte),
+            None => byte,
+        }
+    }
+
+    /// Internal function to get the mapping of the decoded code of the outermost file the code of this tree physically appears in
+    /// to the file itself, if the file was not UTF-8.
+    fn host_transcoding(&self) -> Option<&Transcoding> {
+        match &self.origin {
+            Some(origin) => origin.host_transcoding().map(Arc::as_ref),
+            None => self.transcoding.as_deref(),
+        }
+    }
+
+    /// Internal function to map a byte offset in the code of this tree to the outermost file it physically appears in.
+    pub(crate) fn host_byte(&self, byte: usize) -> usize {
+        let byte = self.host_text_byte(byte);
+        match self.host_transcoding() {
+            Some(transcoding) => transcoding.to_original(byte),
+            None => byte,
+        }
+    }
+
+    /// Internal function to get the code of the outermost file the code of this tree physically appears in.
+    pub(crate) fn host_code(&self) -> &str {
+        match &self.origin {
+            Some(origin) => origin.host_code(),
+            None => self.code.as_str(),
+        }
+    }
+
+    /// Internal function to map a position in the code of this tree, given as a byte offset and a point,
+    /// to the outermost file it physically appears in.
+    pub(crate) fn host_point(&self, byte: usize, point: Point) -> Point {
+        let point = match &self.origin {
+            Some(origin) => origin.host_point(byte),
+            None => point,
+        };
+        match self.host_transcoding() {
+            Some(transcoding) => {
+                let byte = self.host_text_byte(byte);
+                let line_start = transcoding.to_original(byte - point.column);
+                Point::new(point.row, transcoding.to_original(byte) - line_start)
+            }
+            None => point,
+        }
+    }
+
+    /// Internal function to get the decoded code of the line a position of this tree is on in the outermost file it physically appears in,
+    /// up to that position, given as a byte offset and a point in the code of this tree.
+    pub(crate) fn host_line_prefix(&self, byte: usize, point: Point) -> Option<&str> {
+        let point = match &self.origin {
+            Some(origin) => origin.host_point(byte),
+            None => point,
+        };
+        let byte = self.host_text_byte(byte);
+        self.host_code().get(byte.checked_sub(point.column)?..byte)
+    }
+
+    fn make_subtree(
+        &self,
+        node_tree_map: &mut HashMap<usize, Arc<PolyglotTree>>,
+        cyclic_references: &mut HashMap<usize, PathBuf>,
+        session: &BuildSession,
+        node: Node,
+    ) -> bool {
+        let subtree: Arc<PolyglotTree>;
+        let result: Option<Subtree> = match self.language {
             // delegate to language specific subfunction
-            Language::Python => self.make_subtree_python(&node),
-            Language::JavaScript => self.make_subtree_js(&node),
-            Language::Java => self.make_subtree_java(&node),
+            Language::Python => self.make_subtree_python(&node, session),
+            Language::JavaScript => self.make_subtree_js(&node, session),
+            Language::Java => self.make_subtree_java(&node, session),
         };
 
         subtree = match result {
-            Some(t) => t,
+            Some(Subtree::Tree(t)) => t,
+            Some(Subtree::Cycle(file)) => {
+                eprintln!(
+                    "Warning: polyglot call at position {} evaluates file {}, which is already being evaluated; it is marked as a cyclic eval reference",
+                    node.start_position(),
+                    file.display()
+                );
+                cyclic_references.insert(node.id(), file);
+                return true;
+            }
             None => return false,
         };
 
@@ -321,11 +1363,11 @@ impl PolyglotTree {
         true // signal everything went right
     }
 
-    fn make_subtree_python(&self, node: &Node) -> Option<PolyglotTree> {
+    fn make_subtree_python(&self, node: &Node, session: &BuildSession) -> Option<Subtree> {
         let arg1 = node.child(1)?.child(1)?.child(0)?;
         let arg2 = node.child(1)?.child(3)?.child(0)?;
 
-        let mut new_code: Option<String> = None;
+        let mut new_code: Option<(String, Arc<EmbeddedOrigin>)> = None;
         let mut new_lang: Option<String> = None;
         let mut path: Option<PathBuf> = None;
 
@@ -335,7 +1377,7 @@ impl PolyglotTree {
             "path" => {
                 let tmp =
                     util::strip_quotes(self.node_to_code(arg1.next_sibling()?.next_sibling()?));
-                path = Some(self.working_dir.clone());
+                path = Some(PathBuf::new());
                 let new_path = match PathBuf::from_str(tmp.as_str()) {
                     Ok(p) => p,
                     Err(e) => {
@@ -359,9 +1401,7 @@ impl PolyglotTree {
             }
 
             "string" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg1.next_sibling()?.next_sibling()?));
-                new_code = Some(String::from(tmp.as_str()));
+                new_code = Some(self.embedded_code(arg1.next_sibling()?.next_sibling()?));
             }
             other => {
                 eprintln!(
@@ -376,7 +1416,7 @@ impl PolyglotTree {
             "path" => {
                 let tmp =
                     util::strip_quotes(self.node_to_code(arg2.next_sibling()?.next_sibling()?));
-                path = Some(self.working_dir.clone());
+                path = Some(PathBuf::new());
                 let new_path = match PathBuf::from_str(tmp.as_str()) {
                     Ok(p) => p,
                     Err(e) => {
@@ -400,10 +1440,7 @@ impl PolyglotTree {
             }
 
             "string" => {
-                let tmp =
-                    util::strip_quotes(self.node_to_code(arg2.next_sibling()?.next_sibling()?));
-
-                new_code = Some(String::from(tmp.as_str()));
+                new_code = Some(self.embedded_code(arg2.next_sibling()?.next_sibling()?));
             }
 
             other => {
@@ -434,8 +1471,16 @@ impl PolyglotTree {
         };
 
         let subtree = match new_code {
-            Some(c) => Self::from_directory(c, new_lang, self.working_dir.clone())?,
-            None => Self::from_path(
+            Some((c, origin)) => Subtree::Tree(Arc::new(Self::from_directory(
+                c,
+                new_lang,
+                self.working_dir.clone(),
+                self.file.clone(),
+                Some(origin),
+                session,
+                self.eval_chain.clone(),
+            )?)),
+            None => self.file_subtree(
                 // No raw code, check for a path
                 match path {
                     Some(p) => p,
@@ -446,12 +1491,13 @@ impl PolyglotTree {
                     }
                 },
                 new_lang,
+                session,
             )?,
         };
         Some(subtree)
     }
 
-    fn make_subtree_js(&self, node: &Node) -> Option<PolyglotTree> {
+    fn make_subtree_js(&self, node: &Node, session: &BuildSession) -> Option<Subtree> {
         let call_type = node.child(0)?.child(2)?; // function name
         let arg1 = node.child(1)?.child(1)?; // language
         let arg2 = node.child(1)?.child(3)?; // code
@@ -461,7 +1507,6 @@ impl PolyglotTree {
             "eval" => {
                 // Arguments are positional, and always at the same spot
                 let tmp_lang = util::strip_quotes(self.node_to_code(arg1));
-                let tmp_code = util::strip_quotes(self.node_to_code(arg2));
 
                 let new_lang = match util::language_string_to_enum(tmp_lang.as_str()) {
                     Ok(l) => l,
@@ -474,8 +1519,17 @@ impl PolyglotTree {
                     }
                 };
 
-                let new_code = String::from(tmp_code.as_str());
-                Self::from_directory(new_code, new_lang, self.working_dir.clone())
+                let (new_code, origin) = self.embedded_code(arg2);
+                let subtree = Self::from_directory(
+                    new_code,
+                    new_lang,
+                    self.working_dir.clone(),
+                    self.file.clone(),
+                    Some(origin),
+                    session,
+                    self.eval_chain.clone(),
+                )?;
+                Some(Subtree::Tree(Arc::new(subtree)))
             }
 
             "evalFile" => {
@@ -494,7 +1548,7 @@ impl PolyglotTree {
 
                 let tmp_path = util::strip_quotes(self.node_to_code(arg2));
 
-                let mut path = self.working_dir.clone();
+                let mut path = PathBuf::new();
 
                 let new_path = match PathBuf::from_str(tmp_path.as_str()) {
                     Ok(p) => p,
@@ -509,7 +1563,7 @@ impl PolyglotTree {
 
                 path.push(new_path);
 
-                Self::from_path(path, new_lang)
+                self.file_subtree(path, new_lang, session)
             }
 
             other => {
@@ -522,7 +1576,7 @@ impl PolyglotTree {
         }
     }
 
-    fn make_subtree_java(&self, node: &Node) -> Option<PolyglotTree> {
+    fn make_subtree_java(&self, node: &Node, session: &BuildSession) -> Option<Subtree> {
         // Java uses positional arguments, so they will always be accessible with the same route.
         let arg1 = node.child(3)?.child(1)?; // language
         let arg2 = node.child(3)?.child(3)?; // code
@@ -537,7 +1591,16 @@ impl PolyglotTree {
             }
         };
 
-        let new_code = util::strip_quotes(self.node_to_code(arg2));
-        Self::from_directory(new_code, new_lang, self.working_dir.clone())
+        let (new_code, origin) = self.embedded_code(arg2);
+        let subtree = Self::from_directory(
+            new_code,
+            new_lang,
+            self.working_dir.clone(),
+            self.file.clone(),
+            Some(origin),
+            session,
+            self.eval_chain.clone(),
+        )?;
+        Some(Subtree::Tree(Arc::new(subtree)))
     }
 }
@@ -1,5 +1,21 @@
 use super::polyglot_zipper::PolyglotZipper;
+use super::util::Language;
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tree_sitter::Point;
+
+pub mod arity;
+pub mod call_graph;
+pub mod dynamic_eval;
+pub mod interfaces;
+pub mod permissions;
+pub mod reflection;
+pub mod sandbox;
+pub mod slicing;
+pub mod stubs;
+pub mod taint;
+pub mod type_inference;
+mod value_flow;
 
 /// A trait to allow processing over a polyglot tree.
 /// This processing can be any kind of analysis, but starts at the root of the tree.
@@ -80,9 +96,71 @@ impl PolygotProcessor for TreePrinter {
     }
 }
 
+/// The location of a polyglot call, as recorded by the DUBuilder and the ExecutionOrderChecker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingOccurrence {
+    file: Option<PathBuf>,
+    position: Point,
+    language: Language,
+}
+
+impl BindingOccurrence {
+    fn from(zip: &PolyglotZipper) -> BindingOccurrence {
+        BindingOccurrence {
+            file: zip.get_file().map(PathBuf::from),
+            position: zip.start_position(),
+            language: *zip.get_lang(),
+        }
+    }
+
+    /// Returns the file the call appears in, or None if the tree was built from a code snippet.
+    pub fn get_file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the start position of the call.
+    pub fn get_position(&self) -> Point {
+        self.position
+    }
+
+    /// Returns the language the call is written in.
+    pub fn get_lang(&self) -> Language {
+        self.language
+    }
+}
+
+/// The binding names that are used inconsistently across a polyglot tree, as found by the DUBuilder.
+/// Names are sorted alphabetically.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DUInconsistencies {
+    imported_not_exported: Vec<String>,
+    exported_not_imported: Vec<String>,
+}
+
+impl DUInconsistencies {
+    /// Returns the names that are imported somewhere but never exported.
+    pub fn get_imported_not_exported(&self) -> &[String] {
+        self.imported_not_exported.as_slice()
+    }
+
+    /// Returns the names that are exported somewhere but never imported.
+    pub fn get_exported_not_imported(&self) -> &[String] {
+        self.exported_not_imported.as_slice()
+    }
+
+    /// Returns true if every imported name is exported and every exported name is imported.
+    pub fn is_empty(&self) -> bool {
+        self.imported_not_exported.is_empty() && self.exported_not_imported.is_empty()
+    }
+}
+
+/// A processor that records the definitions (exports) and uses (imports) of polyglot bindings across all languages of a tree.
+/// After processing a tree, use the `get_imports`, `get_exports` and `get_inconsistencies` methods to retrieve the results.
+///
+/// Import and export calls whose binding name is not a string literal are ignored.
 pub struct DUBuilder {
-    imports: HashMap<String, HashSet<(u32, u32)>>,
-    exports: HashMap<String, (u32, u32)>,
+    imports: HashMap<String, Vec<BindingOccurrence>>,
+    exports: HashMap<String, Vec<BindingOccurrence>>,
 }
 
 impl Default for DUBuilder {
@@ -92,6 +170,7 @@ impl Default for DUBuilder {
 }
 
 impl DUBuilder {
+    /// Initializes a new DUBuilder instance.
     pub fn new() -> Self {
         DUBuilder {
             imports: HashMap::new(),
@@ -99,51 +178,62 @@ impl DUBuilder {
         }
     }
 
-    fn from(parent: Self) -> Self {
-        DUBuilder {
-            imports: parent.imports,
-            exports: parent.exports,
-        }
+    /// Returns every import of each binding name, in traversal order.
+    pub fn get_imports(&self) -> &HashMap<String, Vec<BindingOccurrence>> {
+        &self.imports
     }
 
-    fn update_maps(&mut self, son: &Self) {
-        self.imports.extend(son.imports.clone().into_iter());
-
-        self.exports.extend(son.exports.clone().into_iter());
+    /// Returns every export of each binding name, in traversal order.
+    pub fn get_exports(&self) -> &HashMap<String, Vec<BindingOccurrence>> {
+        &self.exports
     }
 
-    pub fn get_imports(&self) -> &HashMap<String, HashSet<(u32, u32)>> {
-        &self.imports
-    }
+    /// Returns the names that are imported but never exported, and those that are exported but never imported.
+    pub fn get_inconsistencies(&self) -> DUInconsistencies {
+        let mut imported_not_exported: Vec<String> = self
+            .imports
+            .keys()
+            .filter(|name| !self.exports.contains_key(*name))
+            .cloned()
+            .collect();
+        imported_not_exported.sort();
 
-    pub fn get_exports(&self) -> &HashMap<String, (u32, u32)> {
-        &self.exports
+        let mut exported_not_imported: Vec<String> = self
+            .exports
+            .keys()
+            .filter(|name| !self.imports.contains_key(*name))
+            .cloned()
+            .collect();
+        exported_not_imported.sort();
+
+        DUInconsistencies {
+            imported_not_exported,
+            exported_not_imported,
+        }
     }
 
+    /// Prints the result of `get_inconsistencies` to io::stdout.
     pub fn print_inconsistencies(&self) {
-        for imp in self.imports.keys() {
-            if !self.exports.contains_key(imp) {
-                println!("Imported but not exported: {imp}")
-            }
+        let inconsistencies = self.get_inconsistencies();
+        for imp in inconsistencies.get_imported_not_exported() {
+            println!("Imported but not exported: {imp}")
         }
 
-        for exp in self.exports.keys() {
-            if !self.exports.contains_key(exp) {
-                println!("Exported but not imported: {exp}")
-            }
+        for exp in inconsistencies.get_exported_not_imported() {
+            println!("Exported but not imported: {exp}")
         }
     }
 
     fn process_impl(&mut self, zip: Option<PolyglotZipper>) {
-        if zip.is_some() {
-            let zip = zip.unwrap();
-
-            if zip.is_polyglot_import_call() {
-                todo!()
-            }
-
-            if zip.is_polyglot_export_call() {
-                todo!()
+        if let Some(zip) = zip {
+            if let Ok(name) = zip.get_binding_name() {
+                let map = match zip.is_polyglot_import_call() {
+                    true => &mut self.imports,
+                    false => &mut self.exports,
+                };
+                map.entry(name)
+                    .or_default()
+                    .push(BindingOccurrence::from(&zip));
             }
 
             self.process_impl(zip.child(0));
@@ -154,6 +244,150 @@ impl DUBuilder {
 
 impl PolygotProcessor for DUBuilder {
     fn process(&mut self, zip: PolyglotZipper) {
+        self.imports = HashMap::new();
+        self.exports = HashMap::new();
         self.process_impl(Some(zip));
     }
 }
+
+/// The kinds of polyglot calls whose relative execution order is estimated by the ExecutionOrderChecker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PolyglotCallKind {
+    Eval,
+    Import,
+    Export,
+}
+
+/// A polyglot call, as placed in the estimated execution order by the ExecutionOrderChecker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionEvent {
+    kind: PolyglotCallKind,
+    name: Option<String>,
+    occurrence: BindingOccurrence,
+    deferred: bool,
+}
+
+impl ExecutionEvent {
+    /// Returns the kind of polyglot call.
+    pub fn get_kind(&self) -> PolyglotCallKind {
+        self.kind
+    }
+
+    /// Returns the binding name of an import or export call.
+    /// Returns None for eval calls, and for imports and exports whose name is not a string literal.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the location of the call.
+    pub fn get_occurrence(&self) -> &BindingOccurrence {
+        &self.occurrence
+    }
+
+    /// Returns true if the call is located in the body of a function, and thus only runs if and when that function is called.
+    /// The call is then ordered as if it ran where the function is defined, which is the earliest it possibly could.
+    pub fn is_deferred(&self) -> bool {
+        self.deferred
+    }
+}
+
+/// A processor that estimates the order in which the polyglot calls of a tree are executed.
+/// After processing a tree, use the `get_events` and `get_early_imports` methods to retrieve the results.
+///
+/// The estimation follows source order, and runs the code of an eval call where the call appears,
+/// since GraalVM evaluates it synchronously.
+/// Code in function bodies is marked as deferred, except for the `main` method of Java hosts.
+pub struct ExecutionOrderChecker {
+    events: Vec<ExecutionEvent>,
+}
+
+impl Default for ExecutionOrderChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionOrderChecker {
+    /// Initializes a new ExecutionOrderChecker instance.
+    pub fn new() -> Self {
+        ExecutionOrderChecker { events: Vec::new() }
+    }
+
+    /// Returns every polyglot call of the last processed tree, in estimated execution order.
+    pub fn get_events(&self) -> &[ExecutionEvent] {
+        self.events.as_slice()
+    }
+
+    /// Returns the imports that can execute before any export of the same name,
+    /// in which case GraalVM would import a null value.
+    ///
+    /// An import is considered safe only if an export of its name that is not deferred comes before it in execution order.
+    pub fn get_early_imports(&self) -> Vec<&ExecutionEvent> {
+        let mut exported = HashSet::new();
+        let mut result = Vec::new();
+        for event in self.events.iter() {
+            let name = match event.name.as_deref() {
+                Some(n) => n,
+                None => continue,
+            };
+            match event.kind {
+                PolyglotCallKind::Import if !exported.contains(name) => result.push(event),
+                PolyglotCallKind::Export if !event.deferred => {
+                    exported.insert(name);
+                }
+                _ => (),
+            }
+        }
+        result
+    }
+
+    fn is_entry_point(zip: &PolyglotZipper) -> bool {
+        // Java hosts start running from their main method
+        if !matches!(zip.get_lang(), Language::Java) || !zip.kind().eq("method_declaration") {
+            return false;
+        }
+        let mut child = zip.child(0);
+        while let Some(c) = child {
+            if c.kind().eq("identifier") {
+                return c.code().eq("main");
+            }
+            child = c.next_sibling();
+        }
+        false
+    }
+
+    fn process_impl(&mut self, zip: Option<PolyglotZipper>, deferred: bool) {
+        if let Some(zip) = zip {
+            let kind = if zip.is_polyglot_eval_call() {
+                Some(PolyglotCallKind::Eval)
+            } else if zip.is_polyglot_import_call() {
+                Some(PolyglotCallKind::Import)
+            } else if zip.is_polyglot_export_call() {
+                Some(PolyglotCallKind::Export)
+            } else {
+                None
+            };
+
+            if let Some(kind) = kind {
+                self.events.push(ExecutionEvent {
+                    kind,
+                    name: zip.get_binding_name().ok(),
+                    occurrence: BindingOccurrence::from(&zip),
+                    deferred,
+                });
+            }
+
+            let child_deferred =
+                deferred || (zip.is_function_definition() && !Self::is_entry_point(&zip));
+            self.process_impl(zip.child(0), child_deferred);
+            self.process_impl(zip.next_sibling(), deferred);
+        }
+    }
+}
+
+impl PolygotProcessor for ExecutionOrderChecker {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.events = Vec::new();
+        self.process_impl(Some(zip), false);
+    }
+}
@@ -0,0 +1,264 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::super::util::Language;
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+
+/// The number of arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Arity {
+    required: usize,
+    optional: usize,
+    variadic: bool,
+}
+
+impl Arity {
+    /// Computes the arity of a function definition, or returns None if the contained node is not one.
+    /// Python and JavaScript classes have the arity of their `__init__` or `constructor` method,
+    /// and None if they do not declare one, since they may inherit it.
+    ///
+    /// The implicit `self` or `cls` parameter of Python methods is not counted.
+    pub fn from(def: &PolyglotZipper) -> Option<Arity> {
+        if let Some(constructor) = Self::constructor(def) {
+            return Self::from(&constructor);
+        }
+        if !def.is_function_definition() {
+            return None;
+        }
+        let mut arity = Arity {
+            required: 0,
+            optional: 0,
+            variadic: false,
+        };
+
+        let parameters = ValueIndex::parameters(def);
+        for parameter in parameters.iter() {
+            match parameter.kind() {
+                "default_parameter" | "typed_default_parameter" | "assignment_pattern" => {
+                    arity.optional += 1
+                }
+                "list_splat_pattern"
+                | "dictionary_splat_pattern"
+                | "rest_pattern"
+                | "spread_parameter" => arity.variadic = true,
+                "typed_parameter"
+                    if parameter
+                        .named_children()
+                        .iter()
+                        .any(|c| c.kind().ends_with("splat_pattern")) =>
+                {
+                    arity.variadic = true
+                }
+                "keyword_separator" | "positional_separator" | "receiver_parameter" | "comment" => {
+                }
+                _ => arity.required += 1,
+            }
+        }
+
+        if Self::is_python_method(def) && arity.required > 0 {
+            arity.required -= 1;
+        }
+        Some(arity)
+    }
+
+    /// Returns the `__init__` or `constructor` method declared by a Python or JavaScript class.
+    fn constructor<'a>(class: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+        let name = match (class.get_lang(), class.kind()) {
+            (Language::Python, "class_definition") => "__init__",
+            (Language::JavaScript, "class_declaration" | "class") => "constructor",
+            _ => return None,
+        };
+        class
+            .child_by_field_name("body")?
+            .named_children()
+            .into_iter()
+            .filter_map(|member| match member.kind() {
+                "decorated_definition" => member.child_by_field_name("definition"),
+                _ => Some(member),
+            })
+            .find(|member| {
+                member.is_function_definition()
+                    && ValueIndex::definition_name(member).is_some_and(|n| n.eq(name))
+            })
+    }
+
+    /// Returns true if the definition is a Python method called with an implicit first parameter,
+    /// that is `self` for instance methods or `cls` for class methods, but not static methods.
+    pub(super) fn is_python_method(def: &PolyglotZipper) -> bool {
+        if !matches!(def.get_lang(), Language::Python) || !def.kind().eq("function_definition") {
+            return false;
+        }
+        let definition = match def.parent() {
+            Some(p) if p.kind().eq("decorated_definition") => p,
+            _ => def.clone(),
+        };
+        let is_static = definition
+            .named_children()
+            .iter()
+            .filter(|c| c.kind().eq("decorator"))
+            .any(|d| d.code().trim_start_matches('@').trim() == "staticmethod");
+        !is_static
+            && definition
+                .parent()
+                .and_then(|p| p.parent())
+                .is_some_and(|p| p.kind().eq("class_definition"))
+    }
+
+    /// Returns the minimum number of arguments the function must be called with.
+    pub fn get_min(&self) -> usize {
+        self.required
+    }
+
+    /// Returns the number of parameters with a default value.
+    pub fn get_optional(&self) -> usize {
+        self.optional
+    }
+
+    /// Returns the maximum number of arguments the function can be called with, or None if it is variadic.
+    pub fn get_max(&self) -> Option<usize> {
+        match self.variadic {
+            true => None,
+            false => Some(self.required + self.optional),
+        }
+    }
+
+    /// Returns true if the function can be called with the given number of arguments.
+    pub fn accepts(&self, arguments: usize) -> bool {
+        arguments >= self.get_min() && self.get_max().is_none_or(|max| arguments <= max)
+    }
+}
+
+/// The kinds of problems found by the ArityChecker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallIssueKind {
+    /// The call passes a number of arguments the function does not accept.
+    ArgumentCount { expected: Arity, actual: usize },
+    /// The called value is a literal, which cannot be called.
+    NotCallable,
+}
+
+/// A call through an imported binding or an eval result that does not match the foreign definition it resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallIssue {
+    kind: CallIssueKind,
+    call_site: NodeHandle,
+    definition: NodeHandle,
+}
+
+impl CallIssue {
+    /// Returns the kind of problem.
+    pub fn get_kind(&self) -> &CallIssueKind {
+        &self.kind
+    }
+
+    /// Returns a handle to the call expression.
+    pub fn get_call_site(&self) -> &NodeHandle {
+        &self.call_site
+    }
+
+    /// Returns a handle to the foreign definition or value the callee resolves to.
+    pub fn get_definition(&self) -> &NodeHandle {
+        &self.definition
+    }
+}
+
+/// A processor that checks calls crossing a polyglot boundary against the foreign definitions they resolve to.
+/// After processing a tree, use the `get_issues` method to retrieve the results.
+///
+/// Callees are followed through variables, polyglot imports and the values returned by eval calls
+/// to the function and class definitions they may be, in any language of the tree.
+/// Calls spreading a sequence or mapping into their arguments are not checked for argument count.
+pub struct ArityChecker {
+    issues: Vec<CallIssue>,
+}
+
+impl Default for ArityChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArityChecker {
+    /// Initializes a new ArityChecker instance.
+    pub fn new() -> Self {
+        ArityChecker { issues: Vec::new() }
+    }
+
+    /// Returns every problem found in the last processed tree, in traversal order.
+    pub fn get_issues(&self) -> &[CallIssue] {
+        self.issues.as_slice()
+    }
+
+    fn is_literal(zip: &PolyglotZipper) -> bool {
+        matches!(
+            zip.kind(),
+            "integer"
+                | "float"
+                | "number"
+                | "decimal_integer_literal"
+                | "hex_integer_literal"
+                | "decimal_floating_point_literal"
+                | "string"
+                | "concatenated_string"
+                | "template_string"
+                | "string_literal"
+                | "character_literal"
+                | "true"
+                | "false"
+                | "none"
+                | "null"
+                | "null_literal"
+                | "undefined"
+                | "list"
+                | "tuple"
+                | "dictionary"
+                | "set"
+                | "array"
+                | "object"
+        )
+    }
+
+    fn is_spread(zip: &PolyglotZipper) -> bool {
+        matches!(
+            zip.kind(),
+            "list_splat" | "dictionary_splat" | "spread_element"
+        )
+    }
+}
+
+impl PolygotProcessor for ArityChecker {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let index = ValueIndex::from(zip);
+
+        self.issues = Vec::new();
+        for call in index.calls() {
+            let has_spread = call.arguments.iter().any(Self::is_spread);
+            let callee = match call.callee.as_ref() {
+                Some(c) => c,
+                None => continue,
+            };
+            for target in index.resolve(callee) {
+                if !target.polyglot {
+                    continue;
+                }
+                let kind = if Self::is_literal(&target.value) {
+                    CallIssueKind::NotCallable
+                } else {
+                    match Arity::from(&target.value) {
+                        Some(arity) if !has_spread && !arity.accepts(call.arguments.len()) => {
+                            CallIssueKind::ArgumentCount {
+                                expected: arity,
+                                actual: call.arguments.len(),
+                            }
+                        }
+                        _ => continue,
+                    }
+                };
+                self.issues.push(CallIssue {
+                    kind,
+                    call_site: call.call.to_handle(),
+                    definition: target.value.to_handle(),
+                });
+            }
+        }
+    }
+}
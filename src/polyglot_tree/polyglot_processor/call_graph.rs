@@ -0,0 +1,143 @@
+use super::super::polyglot_zipper::{NodeHandle, NodeKey};
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::HashMap;
+
+/// A function definition in a call graph, in any language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionNode {
+    name: Option<String>,
+    handle: NodeHandle,
+}
+
+impl FunctionNode {
+    /// Returns the name of the function, or None if it is anonymous and not directly assigned to a variable.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns a handle to the function definition.
+    pub fn get_handle(&self) -> &NodeHandle {
+        &self.handle
+    }
+}
+
+/// A call from one function to another in a call graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallEdge {
+    caller: Option<usize>,
+    callee: usize,
+    call_site: NodeHandle,
+    polyglot: bool,
+}
+
+impl CallEdge {
+    /// Returns the index of the calling function, or None if the call is made from top-level code.
+    pub fn get_caller(&self) -> Option<usize> {
+        self.caller
+    }
+
+    /// Returns the index of the called function.
+    pub fn get_callee(&self) -> usize {
+        self.callee
+    }
+
+    /// Returns a handle to the call expression.
+    pub fn get_call_site(&self) -> &NodeHandle {
+        &self.call_site
+    }
+
+    /// Returns true if the callee was reached through an import or an eval call, and thus crosses a polyglot boundary.
+    pub fn is_polyglot(&self) -> bool {
+        self.polyglot
+    }
+}
+
+/// A processor that builds the call graph of a polyglot tree.
+/// After processing a tree, use the `get_functions` and `get_edges` methods to retrieve the results;
+/// edges refer to functions by their index.
+///
+/// Callees are resolved through variables, polyglot imports and exports, and the values returned by eval calls,
+/// such as a JavaScript function evaluated by a Java host and invoked with `Value.execute`.
+/// Each file or eval'd snippet is considered a single scope, and method calls on objects are not resolved.
+pub struct CallGraphBuilder {
+    functions: Vec<FunctionNode>,
+    edges: Vec<CallEdge>,
+}
+
+impl Default for CallGraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallGraphBuilder {
+    /// Initializes a new CallGraphBuilder instance.
+    pub fn new() -> Self {
+        CallGraphBuilder {
+            functions: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Returns every function definition of the last processed tree, in traversal order.
+    pub fn get_functions(&self) -> &[FunctionNode] {
+        self.functions.as_slice()
+    }
+
+    /// Returns every call edge of the last processed tree.
+    pub fn get_edges(&self) -> &[CallEdge] {
+        self.edges.as_slice()
+    }
+
+    /// Returns the edges whose callee is the function at the given index.
+    pub fn get_callers(&self, callee: usize) -> Vec<&CallEdge> {
+        self.edges.iter().filter(|e| e.callee == callee).collect()
+    }
+
+    /// Returns the edges whose caller is the function at the given index.
+    pub fn get_callees(&self, caller: usize) -> Vec<&CallEdge> {
+        self.edges
+            .iter()
+            .filter(|e| e.caller == Some(caller))
+            .collect()
+    }
+}
+
+impl PolygotProcessor for CallGraphBuilder {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let index = ValueIndex::from(zip);
+
+        let mut indices: HashMap<NodeKey, usize> = HashMap::new();
+        self.functions = Vec::new();
+        for function in index.functions() {
+            indices.insert(function.key(), self.functions.len());
+            self.functions.push(FunctionNode {
+                name: ValueIndex::definition_name(function),
+                handle: function.to_handle(),
+            });
+        }
+
+        self.edges = Vec::new();
+        for call in index.calls() {
+            let caller = call
+                .caller
+                .as_ref()
+                .and_then(|c| indices.get(&c.key()).copied());
+            let callee = match call.callee.as_ref() {
+                Some(c) => c,
+                None => continue,
+            };
+            for target in index.resolve(callee) {
+                if let Some(callee) = indices.get(&target.value.key()) {
+                    self.edges.push(CallEdge {
+                        caller,
+                        callee: *callee,
+                        call_site: call.call.to_handle(),
+                        polyglot: target.polyglot,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The arguments of an eval call that may not be statically known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvalArgument {
+    /// The language the code is written in.
+    Language,
+    /// The evaluated code.
+    Code,
+    /// The path of the evaluated file.
+    Path,
+}
+
+/// An argument of an eval call that is not a string literal, and does not resolve to one.
+///
+/// Eval calls may appear in code evaluated from a string literal;
+/// use the `host_position` of the handles to locate them in the file they physically appear in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicEval {
+    eval: NodeHandle,
+    argument: EvalArgument,
+    expression: NodeHandle,
+}
+
+impl DynamicEval {
+    /// Returns a handle to the eval call.
+    pub fn get_eval(&self) -> &NodeHandle {
+        &self.eval
+    }
+
+    /// Returns which argument of the call is dynamic.
+    pub fn get_argument(&self) -> EvalArgument {
+        self.argument
+    }
+
+    /// Returns a handle to the dynamic argument expression.
+    pub fn get_expression(&self) -> &NodeHandle {
+        &self.expression
+    }
+}
+
+/// The number of eval calls of a file whose arguments are all statically known, and of those that have a dynamic argument.
+///
+/// The calls of a file are those that physically appear in it, as given by `PolyglotZipper::get_file`,
+/// including the calls of code it evaluates from string literals, whose sites are located in the file by their host position.
+/// The number of these embedded calls is also reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalCoverage {
+    file: Option<PathBuf>,
+    resolved: usize,
+    unresolved: usize,
+    embedded: usize,
+}
+
+impl EvalCoverage {
+    /// Returns the file the eval calls appear in, or None for code that was not read from a file.
+    pub fn get_file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the number of eval calls whose arguments are all statically known.
+    pub fn get_resolved(&self) -> usize {
+        self.resolved
+    }
+
+    /// Returns the number of eval calls with at least one dynamic argument.
+    pub fn get_unresolved(&self) -> usize {
+        self.unresolved
+    }
+
+    /// Returns the number of eval calls, resolved or not, that appear in code the file evaluates from a string literal rather than in its own code.
+    pub fn get_embedded(&self) -> usize {
+        self.embedded
+    }
+
+    /// Returns the ratio of resolved eval calls to all eval calls of the file.
+    pub fn get_ratio(&self) -> f64 {
+        self.resolved as f64 / (self.resolved + self.unresolved) as f64
+    }
+}
+
+/// A processor that lists the eval calls whose language, code or path cannot be statically known,
+/// which makes the code they evaluate invisible to the polyglot tree.
+/// After processing a tree, use the `get_dynamic_evals` and `get_coverage` methods to retrieve the results,
+/// or `get_summary` for a printable summary of the coverage.
+///
+/// Arguments are considered static if they are string literals, or if every value they may hold is one,
+/// such as a variable only assigned string literals, or an import of a string literal exported by another file.
+pub struct DynamicEvalReporter {
+    dynamic_evals: Vec<DynamicEval>,
+    coverage: Vec<EvalCoverage>,
+}
+
+impl Default for DynamicEvalReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicEvalReporter {
+    /// Initializes a new DynamicEvalReporter instance.
+    pub fn new() -> Self {
+        DynamicEvalReporter {
+            dynamic_evals: Vec::new(),
+            coverage: Vec::new(),
+        }
+    }
+
+    /// Returns every dynamic argument of the eval calls of the last processed tree, in traversal order.
+    pub fn get_dynamic_evals(&self) -> &[DynamicEval] {
+        self.dynamic_evals.as_slice()
+    }
+
+    /// Returns the eval call coverage of each file of the last processed tree, sorted by file.
+    pub fn get_coverage(&self) -> &[EvalCoverage] {
+        self.coverage.as_slice()
+    }
+
+    /// Returns a summary of the coverage of each file, one per line, such as `host.js: 3/4 eval calls resolved (75%)`.
+    pub fn get_summary(&self) -> String {
+        let mut summary = String::new();
+        for coverage in self.coverage.iter() {
+            let file = match coverage.get_file() {
+                Some(f) => f.display().to_string(),
+                None => String::from("<unknown file>"),
+            };
+            summary.push_str(
+                format!(
+                    "{file}: {}/{} eval calls resolved ({:.0}%)\n",
+                    coverage.resolved,
+                    coverage.resolved + coverage.unresolved,
+                    coverage.get_ratio() * 100.0
+                )
+                .as_str(),
+            );
+        }
+        summary
+    }
+
+    fn is_static<'a>(index: &ValueIndex<'a>, argument: &PolyglotZipper<'a>) -> bool {
+        if argument.string_literal_value().is_some() {
+            return true;
+        }
+        let values = index.resolve(argument);
+        !values.is_empty()
+            && values
+                .iter()
+                .all(|v| v.value.string_literal_value().is_some())
+    }
+}
+
+impl PolygotProcessor for DynamicEvalReporter {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let index = ValueIndex::from(zip);
+
+        self.dynamic_evals = Vec::new();
+        let mut coverage: BTreeMap<Option<PathBuf>, EvalCoverage> = BTreeMap::new();
+        for eval in index.evals() {
+            let arguments = match eval.get_eval_arguments() {
+                Some(arguments) => arguments,
+                None => continue,
+            };
+            let source_kind = match arguments.is_file {
+                true => EvalArgument::Path,
+                false => EvalArgument::Code,
+            };
+            let mut resolved = true;
+            for (argument, kind) in [
+                (arguments.language, EvalArgument::Language),
+                (arguments.source, source_kind),
+            ] {
+                if let Some(argument) = argument {
+                    if !Self::is_static(&index, &argument) {
+                        resolved = false;
+                        self.dynamic_evals.push(DynamicEval {
+                            eval: eval.to_handle(),
+                            argument: kind,
+                            expression: argument.to_handle(),
+                        });
+                    }
+                }
+            }
+
+            let file = eval.get_file().map(PathBuf::from);
+            let entry = coverage.entry(file.clone()).or_insert(EvalCoverage {
+                file,
+                resolved: 0,
+                unresolved: 0,
+                embedded: 0,
+            });
+            if eval.tree().is_embedded() {
+                entry.embedded += 1;
+            }
+            match resolved {
+                true => entry.resolved += 1,
+                false => entry.unresolved += 1,
+            }
+        }
+        self.coverage = coverage.into_values().collect();
+    }
+}
@@ -0,0 +1,356 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::super::util::Language;
+use super::type_inference::{self, ValueType};
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::{HashMap, HashSet};
+
+/// The methods of a Java interface, with the interfaces it extends as written in its declaration.
+struct JavaInterface {
+    methods: Vec<(String, NodeHandle)>,
+    implemented: Vec<String>,
+    extends: Vec<String>,
+    scope: JavaScope,
+}
+
+/// The package, imports and enclosing types of a Java type name, which determine the declaration it refers to.
+struct JavaScope {
+    package: Option<String>,
+    imports: Vec<String>,
+    wildcard_imports: Vec<String>,
+    enclosing: Vec<String>,
+}
+
+impl JavaScope {
+    /// Computes the scope of the given node, from the root of its tree down to it.
+    fn of(zip: &PolyglotZipper) -> JavaScope {
+        let mut enclosing = Vec::new();
+        let mut root = zip.clone();
+        while let Some(parent) = root.parent() {
+            if matches!(
+                parent.kind(),
+                "class_declaration" | "interface_declaration" | "enum_declaration"
+            ) {
+                if let Some(name) = ValueIndex::definition_name(&parent) {
+                    enclosing.push(name);
+                }
+            }
+            root = parent;
+        }
+        enclosing.reverse();
+
+        let mut scope = JavaScope {
+            package: None,
+            imports: Vec::new(),
+            wildcard_imports: Vec::new(),
+            enclosing,
+        };
+        for child in root.named_children() {
+            let name = child
+                .named_children()
+                .into_iter()
+                .find(|c| matches!(c.kind(), "scoped_identifier" | "identifier"));
+            let name = match name {
+                Some(name) => String::from(name.code()),
+                None => continue,
+            };
+            match child.kind() {
+                "package_declaration" => scope.package = Some(name),
+                "import_declaration" => {
+                    let code = child.code().trim_end_matches(';');
+                    // static imports name members rather than types
+                    if code.split_whitespace().any(|w| w.eq("static")) {
+                        continue;
+                    }
+                    match code.trim_end().ends_with('*') {
+                        true => scope.wildcard_imports.push(name),
+                        false => scope.imports.push(name),
+                    }
+                }
+                _ => (),
+            }
+        }
+        scope
+    }
+
+    /// Returns the fully qualified names the given type name may refer to, in order of precedence:
+    /// types nested in the enclosing types, single-type imports, types of the same package, on-demand imports,
+    /// and finally the name itself as a fully qualified name.
+    fn candidates(&self, name: &str) -> Vec<String> {
+        let qualify = |prefix: &[&str]| {
+            let mut segments = prefix.to_vec();
+            segments.push(name);
+            segments.join(".")
+        };
+        let mut prefix: Vec<&str> = self.package.iter().map(String::as_str).collect();
+        prefix.extend(self.enclosing.iter().map(String::as_str));
+        let package_len = usize::from(self.package.is_some());
+
+        let mut candidates = Vec::new();
+        for len in (package_len + 1..=prefix.len()).rev() {
+            candidates.push(qualify(&prefix[..len]));
+        }
+        let (first, rest) = match name.find('.') {
+            Some(i) => name.split_at(i),
+            None => (name, ""),
+        };
+        for import in self.imports.iter() {
+            if import.rsplit('.').next() == Some(first) {
+                candidates.push(format!("{import}{rest}"));
+            }
+        }
+        candidates.push(qualify(&prefix[..package_len]));
+        for import in self.wildcard_imports.iter() {
+            candidates.push(format!("{import}.{name}"));
+        }
+        candidates.push(String::from(name));
+        candidates
+    }
+
+    /// Returns the fully qualified name of a type declared with the given simple name in this scope.
+    fn qualified_name(&self, simple_name: &str) -> String {
+        let mut segments: Vec<&str> = self.package.iter().map(String::as_str).collect();
+        segments.extend(self.enclosing.iter().map(String::as_str));
+        segments.push(simple_name);
+        segments.join(".")
+    }
+}
+
+/// A method of a Java interface that the guest value mapped onto it does not provide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingMethod {
+    interface: String,
+    method: String,
+    declaration: NodeHandle,
+    cast: NodeHandle,
+}
+
+impl MissingMethod {
+    /// Returns the simple name of the interface, as written in the cast.
+    pub fn get_interface(&self) -> &str {
+        self.interface.as_str()
+    }
+
+    /// Returns the name of the missing method.
+    pub fn get_method(&self) -> &str {
+        self.method.as_str()
+    }
+
+    /// Returns a handle to the declaration of the method in the interface.
+    pub fn get_declaration(&self) -> &NodeHandle {
+        &self.declaration
+    }
+
+    /// Returns a handle to the `Value.as` call mapping the guest value onto the interface.
+    pub fn get_cast(&self) -> &NodeHandle {
+        &self.cast
+    }
+}
+
+/// A processor that checks the guest values Java hosts map onto interfaces with `Value.as(Interface.class)`
+/// against the declaration of the interface.
+/// After processing a tree, use the `get_missing_methods` method to retrieve the results.
+///
+/// Interfaces are looked up by fully qualified name in the processed tree, then in the Java files of the project,
+/// following the package, imports and enclosing types of the cast as the Java compiler does.
+/// Objects must define every abstract method of the interface, including the ones inherited from the interfaces it extends,
+/// and functions can only be mapped onto interfaces with a single one.
+/// The shape of a cast value is inferred as by the TypeInferrer, from the object, function or class instance it resolves to,
+/// and casts to interfaces outside the project or of values whose shape cannot be worked out are not checked.
+pub struct InterfaceChecker {
+    missing_methods: Vec<MissingMethod>,
+}
+
+impl Default for InterfaceChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterfaceChecker {
+    /// Initializes a new InterfaceChecker instance.
+    pub fn new() -> Self {
+        InterfaceChecker {
+            missing_methods: Vec::new(),
+        }
+    }
+
+    /// Returns every interface method missing from a guest value in the last processed tree, in traversal order.
+    pub fn get_missing_methods(&self) -> &[MissingMethod] {
+        self.missing_methods.as_slice()
+    }
+
+    /// Returns the receiver of a `Value.as` call and the name of the class it maps the value onto, as written.
+    fn cast<'a>(zip: &PolyglotZipper<'a>) -> Option<(PolyglotZipper<'a>, String)> {
+        if !(zip.get_lang() == &Language::Java && zip.kind().eq("method_invocation")) {
+            return None;
+        }
+        if !zip.child_by_field_name("name")?.code().eq("as") {
+            return None;
+        }
+        let object = zip.child_by_field_name("object")?;
+        let class = zip
+            .child_by_field_name("arguments")?
+            .named_children()
+            .into_iter()
+            .find(|a| a.kind().eq("class_literal"))?;
+        let name = class.named_children().into_iter().next()?;
+        Some((object, String::from(name.code())))
+    }
+
+    /// Returns the interfaces extended by an interface declaration, without their type arguments.
+    fn extended_interfaces(interface: &PolyglotZipper) -> Vec<String> {
+        interface
+            .named_children()
+            .into_iter()
+            .filter(|c| c.kind().eq("extends_interfaces"))
+            .flat_map(|e| e.named_children())
+            .filter(|c| c.kind().eq("type_list"))
+            .flat_map(|l| l.named_children())
+            .filter_map(|t| {
+                let name = t.code().split('<').next()?.trim();
+                Some(name.split_whitespace().collect())
+            })
+            .collect()
+    }
+
+    fn is_abstract(method: &PolyglotZipper) -> bool {
+        let modifiers = method
+            .named_children()
+            .into_iter()
+            .find(|c| c.kind().eq("modifiers"));
+        !modifiers.is_some_and(|m| {
+            m.code()
+                .split_whitespace()
+                .any(|w| matches!(w, "default" | "static" | "private"))
+        })
+    }
+
+    fn collect_interfaces(
+        zip: Option<PolyglotZipper>,
+        interfaces: &mut HashMap<String, JavaInterface>,
+    ) {
+        if let Some(zip) = zip {
+            if zip.get_lang() == &Language::Java && zip.kind().eq("interface_declaration") {
+                if let Some(name) = ValueIndex::definition_name(&zip) {
+                    let (abstract_methods, implemented): (Vec<_>, Vec<_>) = zip
+                        .named_children()
+                        .into_iter()
+                        .filter(|c| c.kind().eq("interface_body"))
+                        .flat_map(|b| b.named_children())
+                        .filter(|m| m.kind().eq("method_declaration"))
+                        .partition(Self::is_abstract);
+                    let scope = JavaScope::of(&zip);
+                    // the processed tree is searched first, so its declarations take precedence
+                    interfaces
+                        .entry(scope.qualified_name(name.as_str()))
+                        .or_insert(JavaInterface {
+                            methods: abstract_methods
+                                .iter()
+                                .filter_map(|m| {
+                                    Some((ValueIndex::definition_name(m)?, m.to_handle()))
+                                })
+                                .collect(),
+                            implemented: implemented
+                                .iter()
+                                .filter_map(ValueIndex::definition_name)
+                                .collect(),
+                            extends: Self::extended_interfaces(&zip),
+                            scope,
+                        });
+                }
+            }
+            Self::collect_interfaces(zip.child(0), interfaces);
+            Self::collect_interfaces(zip.next_sibling(), interfaces);
+        }
+    }
+
+    /// Returns the interface the given type name refers to in the given scope, if it is known.
+    fn find<'i>(
+        interfaces: &'i HashMap<String, JavaInterface>,
+        scope: &JavaScope,
+        name: &str,
+    ) -> Option<(&'i String, &'i JavaInterface)> {
+        scope
+            .candidates(name)
+            .iter()
+            .find_map(|candidate| interfaces.get_key_value(candidate))
+    }
+
+    /// Returns the abstract methods of an interface, including the ones inherited from the interfaces it extends
+    /// that it does not implement with a default method.
+    fn abstract_methods<'i>(
+        interfaces: &'i HashMap<String, JavaInterface>,
+        name: &'i String,
+        visited: &mut HashSet<&'i String>,
+    ) -> Vec<&'i (String, NodeHandle)> {
+        let interface = match interfaces.get(name) {
+            Some(interface) if visited.insert(name) => interface,
+            _ => return Vec::new(),
+        };
+        let mut methods: Vec<&'i (String, NodeHandle)> = interface.methods.iter().collect();
+        for parent in interface.extends.iter() {
+            let parent = match Self::find(interfaces, &interface.scope, parent) {
+                Some((parent, _)) => parent,
+                None => continue,
+            };
+            for method in Self::abstract_methods(interfaces, parent, visited) {
+                let is_known = methods.iter().any(|(m, _)| *m == method.0)
+                    || interface.implemented.contains(&method.0);
+                if !is_known {
+                    methods.push(method);
+                }
+            }
+        }
+        methods
+    }
+
+    /// Returns the interface methods the given guest value does not provide.
+    fn missing<'i>(
+        value: &ValueType,
+        methods: Vec<&'i (String, NodeHandle)>,
+    ) -> Vec<&'i (String, NodeHandle)> {
+        match value {
+            ValueType::Object(members) => methods
+                .into_iter()
+                .filter(|(name, _)| !members.iter().any(|(member, _)| member == name))
+                .collect(),
+            ValueType::Function(_) if methods.len() > 1 => methods,
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl PolygotProcessor for InterfaceChecker {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let mut interfaces = HashMap::new();
+        Self::collect_interfaces(Some(zip.clone()), &mut interfaces);
+        for tree in zip.tree().host_class_trees() {
+            Self::collect_interfaces(Some(PolyglotZipper::from(tree)), &mut interfaces);
+        }
+
+        let index = ValueIndex::from(zip);
+        self.missing_methods = Vec::new();
+        for call in index.calls() {
+            let (object, name) = match Self::cast(&call.call) {
+                Some(cast) => cast,
+                None => continue,
+            };
+            let qualified_name = match Self::find(&interfaces, &JavaScope::of(&call.call), &name) {
+                Some((qualified_name, _)) => qualified_name,
+                None => continue,
+            };
+            let methods = Self::abstract_methods(&interfaces, qualified_name, &mut HashSet::new());
+            let value = type_inference::infer_type(&index, &object);
+            let simple_name = name.rsplit('.').next().unwrap_or(name.as_str());
+            for (method, declaration) in Self::missing(&value, methods) {
+                self.missing_methods.push(MissingMethod {
+                    interface: String::from(simple_name),
+                    method: method.clone(),
+                    declaration: declaration.clone(),
+                    cast: call.call.to_handle(),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::super::util::{self, Language};
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::HashSet;
+
+/// A capability a GraalVM Context must be granted for guest code to use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// Granted by `allowIO`, needed to read files such as with `evalFile` or `open`.
+    IO,
+    /// Granted by `allowHostClassLookup`, needed to look up host classes such as with `Java.type`.
+    HostClassLookup,
+    /// Granted by `allowPolyglotAccess`, needed to use the polyglot bindings and evaluate other languages.
+    PolyglotAccess,
+}
+
+/// The kinds of problems found by the ContextPermissionChecker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PermissionIssueKind {
+    /// The guest code needs a permission the Context does not grant.
+    MissingPermission(Permission),
+    /// The code is evaluated in a language the Context does not permit.
+    LanguageNotPermitted(String),
+}
+
+/// A use of a capability by evaluated code that the Context it is evaluated in does not allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionIssue {
+    kind: PermissionIssueKind,
+    context: NodeHandle,
+    eval: NodeHandle,
+    usage: NodeHandle,
+}
+
+impl PermissionIssue {
+    /// Returns the kind of problem.
+    pub fn get_kind(&self) -> &PermissionIssueKind {
+        &self.kind
+    }
+
+    /// Returns a handle to the expression creating the Context, such as a `Context.newBuilder(...)...build()` chain.
+    pub fn get_context(&self) -> &NodeHandle {
+        &self.context
+    }
+
+    /// Returns a handle to the eval call of the Java host.
+    pub fn get_eval(&self) -> &NodeHandle {
+        &self.eval
+    }
+
+    /// Returns a handle to the node needing the permission or language, which is the eval call itself or a node of the evaluated code.
+    pub fn get_usage(&self) -> &NodeHandle {
+        &self.usage
+    }
+}
+
+/// The languages and permissions of a Context, as configured where it is created.
+struct ContextConfig {
+    languages: Vec<String>,
+    permissions: HashSet<Permission>,
+}
+
+impl ContextConfig {
+    fn permits(&self, language: &str) -> bool {
+        self.languages.is_empty()
+            || self
+                .languages
+                .iter()
+                .any(|l| ContextPermissionChecker::same_language(l, language))
+    }
+}
+
+/// A processor that checks the code Java hosts evaluate against the configuration of the Context evaluating it.
+/// After processing a tree, use the `get_issues` method to retrieve the results.
+///
+/// Contexts are read from `Context.create(...)` calls and `Context.newBuilder(...)` chains, with their permitted languages
+/// (none meaning all) and the permissions granted by `allowIO`, `allowHostClassLookup`, `allowPolyglotAccess` and `allowAllAccess`.
+/// The evaluated code, including nested evals, is reported for each permission it needs but is not granted,
+/// and for each language it evaluates that is not permitted; only the first use of each is reported per host eval call.
+/// The Context of a host eval call is found by following its receiver through the variables it is assigned to,
+/// and evals on Contexts it cannot be traced to, such as Contexts created in another class, are not checked.
+pub struct ContextPermissionChecker {
+    issues: Vec<PermissionIssue>,
+}
+
+impl Default for ContextPermissionChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextPermissionChecker {
+    /// Initializes a new ContextPermissionChecker instance.
+    pub fn new() -> Self {
+        ContextPermissionChecker { issues: Vec::new() }
+    }
+
+    /// Returns every problem found in the last processed tree, in traversal order.
+    pub fn get_issues(&self) -> &[PermissionIssue] {
+        self.issues.as_slice()
+    }
+
+    fn same_language(a: &str, b: &str) -> bool {
+        match (
+            util::language_string_to_enum(a),
+            util::language_string_to_enum(b),
+        ) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        }
+    }
+
+    /// Reads the configuration of a Context from the expression creating it, going down the builder chain.
+    fn context_config(value: &PolyglotZipper) -> Option<ContextConfig> {
+        let mut permissions = HashSet::new();
+        let mut node = value.clone();
+        loop {
+            if !node.kind().eq("method_invocation") {
+                return None;
+            }
+            let name = node.child_by_field_name("name")?;
+            let object = node.child_by_field_name("object")?;
+            let arguments = node.child_by_field_name("arguments")?.named_children();
+
+            if object.code().eq("Context") && matches!(name.code(), "newBuilder" | "create") {
+                let languages = arguments
+                    .iter()
+                    .filter_map(|a| a.string_literal_value())
+                    .collect();
+                return Some(ContextConfig {
+                    languages,
+                    permissions,
+                });
+            }
+
+            let granted = arguments.first().is_some_and(|a| {
+                !matches!(
+                    a.code(),
+                    "false" | "null" | "IOAccess.NONE" | "PolyglotAccess.NONE"
+                )
+            });
+            if granted {
+                match name.code() {
+                    "allowIO" => permissions.extend([Permission::IO]),
+                    "allowHostClassLookup" => permissions.extend([Permission::HostClassLookup]),
+                    "allowPolyglotAccess" => permissions.extend([Permission::PolyglotAccess]),
+                    "allowAllAccess" => permissions.extend([
+                        Permission::IO,
+                        Permission::HostClassLookup,
+                        Permission::PolyglotAccess,
+                    ]),
+                    _ => {}
+                }
+            }
+            node = object;
+        }
+    }
+
+    /// Returns the language an eval call evaluates, from its argument or else from the tree it built.
+    fn eval_language(eval: &PolyglotZipper) -> Option<String> {
+        let language = eval
+            .get_eval_arguments()?
+            .language
+            .and_then(|l| l.string_literal_value());
+        match language {
+            Some(language) => Some(language),
+            None => Some(String::from(util::language_enum_to_string(
+                eval.child(0)?.get_lang(),
+            ))),
+        }
+    }
+
+    /// Returns what evaluating code at the given node needs from the Context.
+    fn requirements(zip: &PolyglotZipper, guest: bool) -> Vec<PermissionIssueKind> {
+        let mut requirements = Vec::new();
+        if zip.is_polyglot_eval_call() {
+            let language = Self::eval_language(zip);
+            let own_language = Self::same_language(
+                language.as_deref().unwrap_or_default(),
+                util::language_enum_to_string(zip.get_lang()),
+            );
+            if guest && !own_language {
+                requirements.push(PermissionIssueKind::MissingPermission(
+                    Permission::PolyglotAccess,
+                ));
+            }
+            if zip.get_eval_arguments().is_some_and(|a| a.is_file) {
+                requirements.push(PermissionIssueKind::MissingPermission(Permission::IO));
+            }
+            if let Some(language) = language {
+                requirements.push(PermissionIssueKind::LanguageNotPermitted(language));
+            }
+        } else if guest && (zip.is_polyglot_import_call() || zip.is_polyglot_export_call()) {
+            requirements.push(PermissionIssueKind::MissingPermission(
+                Permission::PolyglotAccess,
+            ));
+        } else if zip.is_host_type_lookup() {
+            requirements.push(PermissionIssueKind::MissingPermission(
+                Permission::HostClassLookup,
+            ));
+        } else if zip.get_lang() == &Language::Python
+            && zip.kind().eq("call")
+            && zip
+                .child_by_field_name("function")
+                .is_some_and(|f| matches!(f.code(), "open" | "io.open"))
+        {
+            requirements.push(PermissionIssueKind::MissingPermission(Permission::IO));
+        }
+        requirements
+    }
+
+    fn check_guest(
+        &mut self,
+        zip: Option<PolyglotZipper>,
+        config: &ContextConfig,
+        site: (&PolyglotZipper, &PolyglotZipper),
+        reported: &mut HashSet<PermissionIssueKind>,
+    ) {
+        if let Some(zip) = zip {
+            for requirement in Self::requirements(&zip, true) {
+                self.check(requirement, &zip, config, site, reported);
+            }
+            self.check_guest(zip.child(0), config, site, reported);
+            self.check_guest(zip.next_sibling(), config, site, reported);
+        }
+    }
+
+    fn check(
+        &mut self,
+        requirement: PermissionIssueKind,
+        usage: &PolyglotZipper,
+        config: &ContextConfig,
+        (context, eval): (&PolyglotZipper, &PolyglotZipper),
+        reported: &mut HashSet<PermissionIssueKind>,
+    ) {
+        let allowed = match &requirement {
+            PermissionIssueKind::MissingPermission(p) => config.permissions.contains(p),
+            PermissionIssueKind::LanguageNotPermitted(l) => config.permits(l),
+        };
+        if !allowed && reported.insert(requirement.clone()) {
+            self.issues.push(PermissionIssue {
+                kind: requirement,
+                context: context.to_handle(),
+                eval: eval.to_handle(),
+                usage: usage.to_handle(),
+            });
+        }
+    }
+}
+
+impl PolygotProcessor for ContextPermissionChecker {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let index = ValueIndex::from(zip);
+
+        self.issues = Vec::new();
+        for eval in index.evals() {
+            if eval.get_lang() != &Language::Java {
+                continue;
+            }
+            let object = match eval.child_by_field_name("object") {
+                Some(object) => object,
+                None => continue,
+            };
+            let context = index
+                .resolve(&object)
+                .into_iter()
+                .find_map(|r| Some((Self::context_config(&r.value)?, r.value)));
+            let (config, context) = match context {
+                Some(context) => context,
+                None => continue,
+            };
+
+            let mut reported = HashSet::new();
+            for requirement in Self::requirements(eval, false) {
+                self.check(requirement, eval, &config, (&context, eval), &mut reported);
+            }
+            self.check_guest(eval.child(0), &config, (&context, eval), &mut reported);
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use super::super::polyglot_zipper::NodeKey;
+use super::super::util::Language;
+use super::super::PolyglotTree;
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+/// Common classes of `java.lang`, which Java code can use without importing them.
+/// Used to tell them apart from classes of packages imported with a wildcard that are not part of the project.
+const JAVA_LANG_CLASSES: [&str; 44] = [
+    "AutoCloseable",
+    "Boolean",
+    "Byte",
+    "CharSequence",
+    "Character",
+    "Class",
+    "ClassLoader",
+    "ClassCastException",
+    "Cloneable",
+    "Comparable",
+    "Double",
+    "Enum",
+    "Error",
+    "Exception",
+    "Float",
+    "IllegalArgumentException",
+    "IllegalStateException",
+    "IndexOutOfBoundsException",
+    "Integer",
+    "InterruptedException",
+    "Iterable",
+    "Long",
+    "Math",
+    "NullPointerException",
+    "Number",
+    "NumberFormatException",
+    "Object",
+    "Process",
+    "ProcessBuilder",
+    "Record",
+    "Runnable",
+    "Runtime",
+    "RuntimeException",
+    "SecurityException",
+    "Short",
+    "StrictMath",
+    "String",
+    "StringBuffer",
+    "StringBuilder",
+    "System",
+    "Thread",
+    "ThreadLocal",
+    "Throwable",
+    "UnsupportedOperationException",
+];
+
+/// A method or constructor registered for reflection, with the fully qualified names of its parameter types.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReflectedMethod {
+    name: String,
+    parameter_types: Vec<String>,
+}
+
+impl ReflectedMethod {
+    /// Returns the name of the method, or `<init>` for constructors.
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the fully qualified names of the parameter types.
+    pub fn get_parameter_types(&self) -> &[String] {
+        self.parameter_types.as_slice()
+    }
+}
+
+/// The host access guest code needs on a Java class.
+///
+/// Members are listed precisely when the class is declared in the project;
+/// otherwise, or when a member is not declared by the class itself, every public constructor, method or field is registered.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReflectedClass {
+    name: String,
+    methods: BTreeSet<ReflectedMethod>,
+    fields: BTreeSet<String>,
+    all_public_constructors: bool,
+    all_public_methods: bool,
+    all_public_fields: bool,
+}
+
+impl ReflectedClass {
+    /// Returns the fully qualified name of the class, with nested classes separated by `$` when the class is declared in the project,
+    /// or as it was looked up otherwise.
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the methods and constructors to register, sorted by name.
+    pub fn get_methods(&self) -> impl Iterator<Item = &ReflectedMethod> {
+        self.methods.iter()
+    }
+
+    /// Returns the names of the fields to register, sorted.
+    pub fn get_fields(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|f| f.as_str())
+    }
+
+    /// Returns true if every public constructor of the class must be registered.
+    pub fn all_public_constructors(&self) -> bool {
+        self.all_public_constructors
+    }
+
+    /// Returns true if every public method of the class must be registered.
+    pub fn all_public_methods(&self) -> bool {
+        self.all_public_methods
+    }
+
+    /// Returns true if every public field of the class must be registered.
+    pub fn all_public_fields(&self) -> bool {
+        self.all_public_fields
+    }
+}
+
+/// A processor that collects the Java classes and members guest code reaches through host type lookups,
+/// and generates the matching reflection configuration for GraalVM native-image.
+/// After processing a tree, use the `get_classes` method to retrieve the results,
+/// and `get_config` or `write_config` to get the `reflect-config.json` file.
+///
+/// Each class is followed from its host type lookup through the variables it is assigned to, the parameters it is passed to
+/// and the calls returning it, in any language of the tree.
+/// Every member read or called on the class or its instances is registered, as well as constructors for instantiated classes.
+pub struct ReflectionConfigBuilder {
+    classes: Vec<ReflectedClass>,
+}
+
+impl Default for ReflectionConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReflectionConfigBuilder {
+    /// Initializes a new ReflectionConfigBuilder instance.
+    pub fn new() -> Self {
+        ReflectionConfigBuilder {
+            classes: Vec::new(),
+        }
+    }
+
+    /// Returns every host class used by guest code in the last processed tree, sorted by name.
+    pub fn get_classes(&self) -> &[ReflectedClass] {
+        self.classes.as_slice()
+    }
+
+    /// Returns the reflection configuration of the last processed tree, in the JSON format of `reflect-config.json`.
+    pub fn get_config(&self) -> String {
+        let mut entries = Vec::new();
+        for class in self.classes.iter() {
+            let mut properties = vec![format!("\"name\": {}", Self::json_string(&class.name))];
+            for (flag, name) in [
+                (class.all_public_constructors, "allPublicConstructors"),
+                (class.all_public_methods, "allPublicMethods"),
+                (class.all_public_fields, "allPublicFields"),
+            ] {
+                if flag {
+                    properties.push(format!("\"{name}\": true"));
+                }
+            }
+            if !class.methods.is_empty() {
+                let methods: Vec<String> = class
+                    .methods
+                    .iter()
+                    .map(|m| {
+                        let types: Vec<String> = m
+                            .parameter_types
+                            .iter()
+                            .map(|t| Self::json_string(t))
+                            .collect();
+                        format!(
+                            "      {{ \"name\": {}, \"parameterTypes\": [{}] }}",
+                            Self::json_string(&m.name),
+                            types.join(", ")
+                        )
+                    })
+                    .collect();
+                properties.push(format!("\"methods\": [\n{}\n    ]", methods.join(",\n")));
+            }
+            if !class.fields.is_empty() {
+                let fields: Vec<String> = class
+                    .fields
+                    .iter()
+                    .map(|f| format!("      {{ \"name\": {} }}", Self::json_string(f)))
+                    .collect();
+                properties.push(format!("\"fields\": [\n{}\n    ]", fields.join(",\n")));
+            }
+            entries.push(format!("  {{\n    {}\n  }}", properties.join(",\n    ")));
+        }
+        match entries.is_empty() {
+            true => String::from("[]\n"),
+            false => format!("[\n{}\n]\n", entries.join(",\n")),
+        }
+    }
+
+    /// Writes the reflection configuration of the last processed tree to the given file.
+    pub fn write_config(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.get_config())
+    }
+
+    fn json_string(s: &str) -> String {
+        let mut result = String::from("\"");
+        for c in s.chars() {
+            match c {
+                '"' => result.push_str("\\\""),
+                '\\' => result.push_str("\\\\"),
+                c if c.is_control() => result.push_str(format!("\\u{:04x}", c as u32).as_str()),
+                c => result.push(c),
+            }
+        }
+        result.push('"');
+        result
+    }
+
+    /// Returns the name of the member the parent of `zip` accesses on it, such as `greet` in `greeter.greet`.
+    fn accessed_member(zip: &PolyglotZipper) -> Option<String> {
+        let parent = zip.parent()?;
+        let (object, member) = match parent.kind() {
+            "member_expression" => ("object", "property"),
+            "attribute" => ("object", "attribute"),
+            _ => return None,
+        };
+        if parent.child_by_field_name(object).as_ref() != Some(zip) {
+            return None;
+        }
+        Some(String::from(parent.child_by_field_name(member)?.code()))
+    }
+
+    /// Returns the expression creating an instance of the class `zip` holds, such as `new Greeter()` or `Greeter()`.
+    fn instantiation<'a>(zip: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+        let parent = zip.parent()?;
+        let field = match (zip.get_lang(), parent.kind()) {
+            (Language::JavaScript, "new_expression") => "constructor",
+            (Language::Python, "call") => "function",
+            _ => return None,
+        };
+        match parent.child_by_field_name(field).as_ref() == Some(zip) {
+            true => Some(parent),
+            false => None,
+        }
+    }
+
+    /// Follows the class looked up by `lookup` and its instances through the tree,
+    /// and returns whether the class is instantiated and the names of the members accessed on them.
+    fn follow<'a>(
+        index: &ValueIndex<'a>,
+        lookup: &PolyglotZipper<'a>,
+        edges: &HashMap<NodeKey, Vec<PolyglotZipper<'a>>>,
+    ) -> (bool, BTreeSet<String>) {
+        let mut queue = VecDeque::from([lookup.clone()]);
+        if lookup.kind().eq("host_type_lookup")
+            && lookup.parent().is_some_and(|p| p.kind().contains("import"))
+        {
+            // names imported from the `java` module are used directly rather than assigned
+            let alias = lookup
+                .parent()
+                .filter(|p| p.kind().eq("aliased_import"))
+                .and_then(|p| p.child_by_field_name("alias"));
+            let name = match &alias {
+                Some(alias) => Some(alias.code()),
+                None => lookup.code().rsplit('.').next(),
+            };
+            if let Some(name) = name {
+                queue.extend(index.uses(lookup, name).iter().cloned());
+            }
+        }
+        let mut visited: HashSet<NodeKey> = queue.iter().map(PolyglotZipper::key).collect();
+        let mut instantiated = false;
+        let mut members = BTreeSet::new();
+
+        while let Some(node) = queue.pop_front() {
+            if let Some(member) = Self::accessed_member(&node) {
+                members.insert(member);
+            }
+            let instance = Self::instantiation(&node);
+            instantiated |= instance.is_some();
+            let successors = edges
+                .get(&node.key())
+                .into_iter()
+                .flatten()
+                .chain(instance.iter());
+            for next in successors {
+                if visited.insert(next.key()) {
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+        (instantiated, members)
+    }
+
+    /// Returns the fully qualified name of a type written in a Java file, with nested classes separated by `$`.
+    ///
+    /// A written name starting with a class, such as `Map.Entry`, is qualified through that class,
+    /// as found by `qualified_class` in the file and the `project` it belongs to.
+    fn qualified_type(
+        type_node: &PolyglotZipper,
+        root: &PolyglotZipper,
+        project: &[PolyglotTree],
+    ) -> String {
+        let code: String = type_node.code().split_whitespace().collect();
+        let dimensions = code.matches("[]").count() + code.matches("...").count();
+        let written = match code.find(['<', '[']) {
+            Some(end) => code[..end].trim_end_matches("..."),
+            None => code.trim_end_matches("..."),
+        };
+        let (first, nested) = match written.split_once('.') {
+            Some((first, nested)) => (first, Some(nested)),
+            None => (written, None),
+        };
+
+        let qualified = if matches!(
+            written,
+            "int" | "long" | "short" | "byte" | "char" | "float" | "double" | "boolean"
+        ) || first.starts_with(|c: char| c.is_ascii_lowercase())
+        {
+            // primitive types and names written with their package
+            String::from(written)
+        } else {
+            let outer = Self::qualified_class(first, root, project);
+            match nested {
+                Some(nested) => format!("{outer}${}", nested.replace('.', "$")),
+                None => outer,
+            }
+        };
+        format!("{qualified}{}", "[]".repeat(dimensions))
+    }
+
+    /// Returns the fully qualified name of the class with the given simple name, as seen from the Java file of `root`.
+    ///
+    /// The class is looked up in the single-type imports of the file, then in its package, then in its wildcard imports.
+    /// Classes of the package and of wildcard imports are only found if they are declared in the file or in the project.
+    /// Otherwise, the class is taken from `java.lang`, unless a wildcard import provides classes that are not known to be part of it.
+    fn qualified_class(
+        simple_name: &str,
+        root: &PolyglotZipper,
+        project: &[PolyglotTree],
+    ) -> String {
+        let children = root.named_children();
+        let mut wildcards = Vec::new();
+        for import in children
+            .iter()
+            .filter(|c| c.kind().eq("import_declaration"))
+        {
+            if import.child(1).is_some_and(|c| c.code().eq("static")) {
+                continue;
+            }
+            let parts = import.named_children();
+            let name = match parts.first() {
+                Some(name) => name.code(),
+                None => continue,
+            };
+            if parts.iter().any(|p| p.kind().eq("asterisk")) {
+                wildcards.push(name);
+            } else if name.rsplit('.').next() == Some(simple_name) {
+                return String::from(name);
+            }
+        }
+
+        let declared = |qualified: &str| {
+            root.tree().declares_type(qualified)
+                || project.iter().any(|t| t.declares_type(qualified))
+        };
+        let package = children
+            .iter()
+            .find(|c| c.kind().eq("package_declaration"))
+            .and_then(|p| p.named_children().into_iter().next());
+        let same_package = match package {
+            Some(package) => format!("{}.{simple_name}", package.code()),
+            None => String::from(simple_name),
+        };
+        if declared(same_package.as_str()) {
+            return same_package;
+        }
+        let mut candidates = wildcards.iter().map(|w| format!("{w}.{simple_name}"));
+        if let Some(qualified) = candidates.clone().find(|q| declared(q)) {
+            return qualified;
+        }
+        match candidates.next() {
+            Some(qualified) if !JAVA_LANG_CLASSES.contains(&simple_name) => qualified,
+            _ => format!("java.lang.{simple_name}"),
+        }
+    }
+
+    fn parameter_types(
+        declaration: &PolyglotZipper,
+        root: &PolyglotZipper,
+        project: &[PolyglotTree],
+    ) -> Vec<String> {
+        declaration
+            .named_children()
+            .into_iter()
+            .filter(|c| c.kind().eq("formal_parameters"))
+            .flat_map(|p| p.named_children())
+            .filter(|p| matches!(p.kind(), "formal_parameter" | "spread_parameter"))
+            .filter_map(|p| {
+                let children = p.named_children();
+                let type_node = children.iter().find(|c| !c.kind().eq("modifiers"))?;
+                let mut qualified = Self::qualified_type(type_node, root, project);
+                if p.kind().eq("spread_parameter") {
+                    qualified.push_str("[]");
+                }
+                Some(qualified)
+            })
+            .collect()
+    }
+
+    /// Registers the constructors and members used on a class, listing them precisely if its declaration is known.
+    fn register(
+        class: &mut ReflectedClass,
+        declaration: Option<PolyglotZipper>,
+        instantiated: bool,
+        members: &BTreeSet<String>,
+        project: &[PolyglotTree],
+    ) {
+        let declaration = match declaration {
+            Some(d) => d,
+            None => {
+                class.all_public_constructors |= instantiated;
+                class.all_public_methods |= !members.is_empty();
+                class.all_public_fields |= !members.is_empty();
+                return;
+            }
+        };
+        let root = PolyglotZipper::from(declaration.tree());
+        let body = declaration
+            .named_children()
+            .into_iter()
+            .filter(|c| matches!(c.kind(), "class_body" | "interface_body" | "enum_body"))
+            .flat_map(|b| b.named_children());
+
+        let mut found = BTreeSet::new();
+        let mut has_constructor = false;
+        for member in body {
+            match member.kind() {
+                "constructor_declaration" => {
+                    has_constructor = true;
+                    if instantiated {
+                        class.methods.insert(ReflectedMethod {
+                            name: String::from("<init>"),
+                            parameter_types: Self::parameter_types(&member, &root, project),
+                        });
+                    }
+                }
+                "method_declaration" => {
+                    if let Some(name) = ValueIndex::definition_name(&member) {
+                        if members.contains(&name) {
+                            found.insert(name.clone());
+                            class.methods.insert(ReflectedMethod {
+                                name,
+                                parameter_types: Self::parameter_types(&member, &root, project),
+                            });
+                        }
+                    }
+                }
+                "field_declaration" | "constant_declaration" => {
+                    for declarator in member
+                        .named_children()
+                        .into_iter()
+                        .filter(|c| c.kind().eq("variable_declarator"))
+                    {
+                        if let Some(name) = declarator.named_children().into_iter().next() {
+                            if members.contains(name.code()) {
+                                found.insert(String::from(name.code()));
+                                class.fields.insert(String::from(name.code()));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if instantiated && !has_constructor {
+            // the implicit default constructor
+            class.methods.insert(ReflectedMethod {
+                name: String::from("<init>"),
+                parameter_types: Vec::new(),
+            });
+        }
+        if members.iter().any(|m| !found.contains(m)) {
+            // members inherited from classes outside the declaration
+            class.all_public_methods = true;
+            class.all_public_fields = true;
+        }
+    }
+
+    /// Returns the binary name of a declared class, with its package and the classes it is nested in separated by `$`,
+    /// which is the name reflection expects for nested classes even if they were looked up as `Outer.Inner`.
+    fn binary_name(declaration: &PolyglotZipper) -> Option<String> {
+        let mut names = vec![ValueIndex::definition_name(declaration)?];
+        let mut root = declaration.clone();
+        while let Some(parent) = root.parent() {
+            if matches!(
+                parent.kind(),
+                "class_declaration" | "interface_declaration" | "enum_declaration"
+            ) {
+                names.push(ValueIndex::definition_name(&parent)?);
+            }
+            root = parent;
+        }
+        names.reverse();
+        let class = names.join("$");
+        let package = root
+            .named_children()
+            .into_iter()
+            .find(|c| c.kind().eq("package_declaration"))
+            .and_then(|p| p.named_children().into_iter().next());
+        Some(match package {
+            Some(package) => format!("{}.{class}", package.code()),
+            None => class,
+        })
+    }
+
+    fn collect_lookups<'a>(zip: Option<PolyglotZipper<'a>>, lookups: &mut Vec<PolyglotZipper<'a>>) {
+        if let Some(zip) = zip {
+            if zip.is_host_type_lookup() {
+                lookups.push(zip.clone());
+            }
+            Self::collect_lookups(zip.child(0), lookups);
+            Self::collect_lookups(zip.next_sibling(), lookups);
+        }
+    }
+}
+
+impl PolygotProcessor for ReflectionConfigBuilder {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let mut lookups = Vec::new();
+        Self::collect_lookups(Some(zip.clone()), &mut lookups);
+
+        let project = zip.tree().host_class_trees();
+        let index = ValueIndex::from(zip);
+        let edges = index.flow_edges();
+        let mut classes: BTreeMap<String, ReflectedClass> = BTreeMap::new();
+        for lookup in lookups.iter() {
+            let name = match lookup.get_host_type_name() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            let declaration = lookup.host_type_declaration();
+            let name = match declaration.as_ref().and_then(Self::binary_name) {
+                Some(binary_name) => binary_name,
+                None => name,
+            };
+            let (instantiated, members) = Self::follow(&index, lookup, &edges);
+            let class = classes
+                .entry(name.clone())
+                .or_insert_with(|| ReflectedClass {
+                    name,
+                    ..Default::default()
+                });
+            Self::register(class, declaration, instantiated, &members, project);
+        }
+        self.classes = classes.into_values().collect();
+    }
+}
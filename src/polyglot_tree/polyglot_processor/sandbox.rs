@@ -0,0 +1,155 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::super::util::Language;
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::path::Path;
+
+/// The dangerous operations flagged by the SandboxLinter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SandboxRule {
+    /// A Python shell command, with `os.system` or `os.popen`.
+    ShellCommand,
+    /// A call to the Python `subprocess` module.
+    Subprocess,
+    /// A Python `eval` or `exec` of code that is not a string literal.
+    DynamicEval,
+    /// A JavaScript `require('child_process')`.
+    ChildProcess,
+    /// A host lookup of `java.lang.Runtime`, which can run commands and stop the host.
+    RuntimeAccess,
+}
+
+/// A dangerous operation found in embedded code, with the eval calls it was embedded through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxFinding {
+    rule: SandboxRule,
+    node: NodeHandle,
+    chain: Vec<NodeHandle>,
+}
+
+impl SandboxFinding {
+    /// Returns the rule the operation breaks.
+    pub fn get_rule(&self) -> SandboxRule {
+        self.rule
+    }
+
+    /// Returns a handle to the dangerous call or lookup.
+    pub fn get_node(&self) -> &NodeHandle {
+        &self.node
+    }
+
+    /// Returns the eval calls the code was embedded through, from the one in the host file to the innermost one.
+    pub fn get_chain(&self) -> &[NodeHandle] {
+        self.chain.as_slice()
+    }
+
+    /// Returns the host file the embedding chain starts from, if the tree was built from a file.
+    pub fn get_host_file(&self) -> Option<&Path> {
+        self.chain.first()?.get_file()
+    }
+}
+
+/// A processor that flags dangerous operations in embedded code, that is code evaluated through an eval call.
+/// After processing a tree, use the `get_findings` method to retrieve the results.
+///
+/// The code of the host file itself is not checked, only the subtrees it embeds at any depth.
+/// Python calls are matched after resolving the names imported by the embedded code,
+/// so that `from subprocess import run` followed by `run(...)` is flagged as well.
+pub struct SandboxLinter {
+    findings: Vec<SandboxFinding>,
+}
+
+impl Default for SandboxLinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SandboxLinter {
+    /// Initializes a new SandboxLinter instance.
+    pub fn new() -> Self {
+        SandboxLinter {
+            findings: Vec::new(),
+        }
+    }
+
+    /// Returns every dangerous operation found in the last processed tree, in traversal order.
+    pub fn get_findings(&self) -> &[SandboxFinding] {
+        self.findings.as_slice()
+    }
+
+    fn first_argument<'a>(call: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+        call.child_by_field_name("arguments")?
+            .named_children()
+            .into_iter()
+            .find(|a| !a.kind().eq("comment"))
+    }
+
+    fn rule<'a>(index: &ValueIndex<'a>, zip: &PolyglotZipper<'a>) -> Option<SandboxRule> {
+        if zip.is_host_type_lookup() {
+            return match zip.get_host_type_name().ok()?.as_str() {
+                "java.lang.Runtime" => Some(SandboxRule::RuntimeAccess),
+                _ => None,
+            };
+        }
+        let function = match (zip.get_lang(), zip.kind()) {
+            (Language::Python, "call") | (Language::JavaScript, "call_expression") => {
+                zip.child_by_field_name("function")?
+            }
+            _ => return None,
+        };
+        match (zip.get_lang(), index.qualified_name(&function).as_str()) {
+            (Language::Python, "os.system" | "os.popen") => Some(SandboxRule::ShellCommand),
+            (Language::Python, f) if f.starts_with("subprocess.") => Some(SandboxRule::Subprocess),
+            (Language::Python, "eval" | "exec") => {
+                match Self::first_argument(zip)?.string_literal_value() {
+                    Some(_) => None,
+                    None => Some(SandboxRule::DynamicEval),
+                }
+            }
+            (Language::JavaScript, "require") => {
+                match Self::first_argument(zip)?.string_literal_value()?.as_str() {
+                    "child_process" | "node:child_process" => Some(SandboxRule::ChildProcess),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn process_impl<'a>(
+        &mut self,
+        index: &ValueIndex<'a>,
+        zip: Option<PolyglotZipper<'a>>,
+        chain: &mut Vec<PolyglotZipper<'a>>,
+    ) {
+        if let Some(zip) = zip {
+            if !chain.is_empty() {
+                if let Some(rule) = Self::rule(index, &zip) {
+                    self.findings.push(SandboxFinding {
+                        rule,
+                        node: zip.to_handle(),
+                        chain: chain.iter().map(|e| e.to_handle()).collect(),
+                    });
+                }
+            }
+
+            if zip.is_polyglot_eval_call() {
+                chain.push(zip.clone());
+                self.process_impl(index, zip.child(0), chain);
+                chain.pop();
+            } else {
+                self.process_impl(index, zip.child(0), chain);
+            }
+            self.process_impl(index, zip.next_sibling(), chain);
+        }
+    }
+}
+
+impl PolygotProcessor for SandboxLinter {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.findings = Vec::new();
+        let index = ValueIndex::from(zip.clone());
+        self.process_impl(&index, Some(zip), &mut Vec::new());
+    }
+}
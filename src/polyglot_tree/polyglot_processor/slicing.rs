@@ -0,0 +1,159 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::super::polyglot_zipper::NodeKey;
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::{HashMap, HashSet};
+
+/// A processor that computes the backward slice of a node: every statement, in any language, that can affect it.
+/// After processing a tree, use the `get_slice` method to retrieve the results.
+///
+/// A statement is in the slice if a value it computes flows to the node, following the same flows as the TaintAnalyzer,
+/// or if it controls whether the node runs: the conditions of enclosing `if` and loop statements,
+/// and the eval calls that run the code the node is embedded in.
+pub struct BackwardSlicer {
+    criterion: NodeHandle,
+    slice: Vec<NodeHandle>,
+}
+
+impl BackwardSlicer {
+    /// Initializes a new BackwardSlicer instance for the node of the given handle, usually obtained with `PolyglotZipper::to_handle`.
+    pub fn new(criterion: NodeHandle) -> BackwardSlicer {
+        BackwardSlicer {
+            criterion,
+            slice: Vec::new(),
+        }
+    }
+
+    /// Returns the statements of the slice computed on the last processed tree, sorted by file and position.
+    /// The slice is empty if the tree does not contain the node this slicer was built for.
+    pub fn get_slice(&self) -> &[NodeHandle] {
+        self.slice.as_slice()
+    }
+
+    /// Returns true if the slice computed on the last processed tree contains the statement of the given handle.
+    pub fn contains(&self, handle: &NodeHandle) -> bool {
+        self.slice.contains(handle)
+    }
+
+    fn find_criterion<'a>(&self, zip: Option<PolyglotZipper<'a>>) -> Option<PolyglotZipper<'a>> {
+        let zip = zip?;
+        if self.criterion.is_handle_of(&zip) {
+            return Some(zip);
+        }
+        self.find_criterion(zip.child(0))
+            .or_else(|| self.find_criterion(zip.next_sibling()))
+    }
+
+    fn is_block(zip: &PolyglotZipper) -> bool {
+        matches!(
+            zip.kind(),
+            "module"
+                | "program"
+                | "block"
+                | "statement_block"
+                | "class_body"
+                | "constructor_body"
+                | "switch_body"
+                | "switch_block"
+        )
+    }
+
+    /// Returns the statement enclosing the given node,
+    /// or the condition itself for nodes in the condition of an `if` or loop statement.
+    fn statement<'a>(zip: &PolyglotZipper<'a>) -> PolyglotZipper<'a> {
+        let mut current = zip.clone();
+        while let Some(parent) = current.parent() {
+            if Self::is_block(&parent) {
+                break;
+            }
+            if parent.child_by_field_name("condition").as_ref() == Some(&current)
+                && parent.kind().ends_with("_statement")
+            {
+                break;
+            }
+            current = parent;
+        }
+        current
+    }
+
+    /// Returns the conditions of the `if` and loop statements enclosing the given node.
+    fn conditions<'a>(zip: &PolyglotZipper<'a>) -> Vec<PolyglotZipper<'a>> {
+        let mut result = Vec::new();
+        let mut current = zip.clone();
+        while let Some(parent) = current.parent() {
+            if parent.kind().ends_with("_statement") || parent.kind().ends_with("_clause") {
+                if let Some(condition) = parent.child_by_field_name("condition") {
+                    if condition != current {
+                        result.push(condition);
+                    }
+                }
+            }
+            current = parent;
+        }
+        result
+    }
+
+    fn root<'a>(zip: &PolyglotZipper<'a>) -> PolyglotZipper<'a> {
+        let mut current = zip.clone();
+        while let Some(parent) = current.parent() {
+            current = parent;
+        }
+        current
+    }
+}
+
+impl PolygotProcessor for BackwardSlicer {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.slice = Vec::new();
+        let criterion = match self.find_criterion(Some(zip.clone())) {
+            Some(c) => c,
+            None => return,
+        };
+
+        let index = ValueIndex::from(zip);
+        let mut dependencies: HashMap<NodeKey, Vec<PolyglotZipper>> = HashMap::new();
+        for (from, to) in index.flow_edge_list() {
+            dependencies.entry(to.key()).or_default().push(from);
+        }
+        let eval_of_root: HashMap<NodeKey, PolyglotZipper> = index
+            .evals()
+            .iter()
+            .filter_map(|e| Some((e.child(0)?.key(), e.clone())))
+            .collect();
+
+        let mut visited = HashSet::from([criterion.key()]);
+        let mut statements = HashMap::new();
+        let mut worklist = vec![criterion];
+        while let Some(node) = worklist.pop() {
+            let mut next = dependencies.get(&node.key()).cloned().unwrap_or_default();
+            if !node.is_function_definition() {
+                // expressions depend on their subexpressions, but definitions do not depend on their body
+                next.extend(node.named_children());
+            }
+
+            let statement = Self::statement(&node);
+            if statements
+                .insert(statement.key(), statement.clone())
+                .is_none()
+            {
+                next.extend(Self::conditions(&statement));
+                if let Some(eval) = eval_of_root.get(&Self::root(&statement).key()) {
+                    next.push(eval.clone());
+                }
+            }
+
+            for n in next {
+                if visited.insert(n.key()) {
+                    worklist.push(n);
+                }
+            }
+        }
+
+        let mut slice: Vec<NodeHandle> = statements.values().map(|s| s.to_handle()).collect();
+        slice.sort_by(|a, b| {
+            (a.get_file(), a.host_byte_range().start)
+                .cmp(&(b.get_file(), b.host_byte_range().start))
+        });
+        self.slice = slice;
+    }
+}
@@ -0,0 +1,425 @@
+use super::type_inference::{Signature, TypeInferrer, ValueType};
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The keywords of Python, which cannot name the members of a stub.
+const PYTHON_KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// The reserved words of TypeScript, which cannot name parameters.
+/// Unlike those of Python, they can name members.
+const TYPESCRIPT_RESERVED_WORDS: [&str; 45] = [
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "implements",
+    "interface",
+    "let",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "static",
+    "yield",
+];
+
+/// The Protocols and classes declared by the Python stubs, each named after the path of the value it types.
+#[derive(Default)]
+struct PythonClasses {
+    names: HashSet<String>,
+    declarations: Vec<String>,
+}
+
+impl PythonClasses {
+    /// Returns an unused class name for the value at the given path,
+    /// adding a counter to the name when another path maps to the same one.
+    fn reserve(&mut self, path: &str) -> String {
+        let base = format!(
+            "_{}",
+            path.chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+        );
+        let mut name = base.clone();
+        let mut counter = 1;
+        while self.names.contains(&name) {
+            counter += 1;
+            name = format!("{base}_{counter}");
+        }
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+/// A processor that generates type declarations for the values shared through polyglot bindings,
+/// so that editors can offer completion on imported values.
+/// After processing a tree, use the `get_typescript` and `get_python` methods to retrieve the generated code,
+/// or `write_to` to write both to a directory.
+///
+/// The TypeScript declarations are a module exporting a `PolyglotBindings` interface with every exported binding name,
+/// which also declares the global `Polyglot` object of GraalJS with an `import` function typed after it.
+/// The Python stubs type `polyglot.import_value` in the same way, so they should be saved as `polyglot.pyi`.
+/// Values are typed with the shapes found by the TypeInferrer, and anything it could not infer is typed as `any`.
+pub struct StubGenerator {
+    typescript: String,
+    python: String,
+}
+
+impl Default for StubGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StubGenerator {
+    /// Initializes a new StubGenerator instance.
+    pub fn new() -> Self {
+        StubGenerator {
+            typescript: String::new(),
+            python: String::new(),
+        }
+    }
+
+    /// Returns the TypeScript declarations generated for the last processed tree, to be saved in a `.d.ts` file.
+    pub fn get_typescript(&self) -> &str {
+        self.typescript.as_str()
+    }
+
+    /// Returns the Python stubs generated for the last processed tree, to be saved in a `.pyi` file.
+    pub fn get_python(&self) -> &str {
+        self.python.as_str()
+    }
+
+    /// Writes the generated declarations to `polyglot.d.ts` and `polyglot.pyi` in the given directory.
+    pub fn write_to(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::write(dir.join("polyglot.d.ts"), self.get_typescript())?;
+        std::fs::write(dir.join("polyglot.pyi"), self.get_python())
+    }
+
+    fn quote(s: &str) -> String {
+        let mut result = String::from("\"");
+        for c in s.chars() {
+            match c {
+                '"' | '\\' => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                '\n' => result.push_str("\\n"),
+                c => result.push(c),
+            }
+        }
+        result.push('"');
+        result
+    }
+
+    /// Reserved words are valid property names in TypeScript, so only the characters are checked.
+    fn is_typescript_identifier(s: &str) -> bool {
+        let mut chars = s.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    }
+
+    fn is_python_identifier(s: &str) -> bool {
+        let mut chars = s.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+            && !PYTHON_KEYWORDS.contains(&s)
+    }
+
+    /// Names parameters after the definition, falling back to `argN` when they are not plain names
+    /// or cannot name a parameter in the target language, as given by `is_valid`.
+    fn parameter_names(signature: &Signature, is_valid: impl Fn(&str) -> bool) -> Vec<String> {
+        let arity = signature.get_arity();
+        (0..arity.get_min() + arity.get_optional())
+            .map(|i| match signature.get_parameters().get(i) {
+                Some(name) if is_valid(name) => name.clone(),
+                _ => format!("arg{i}"),
+            })
+            .collect()
+    }
+
+    /// `args` is left for the rest parameter.
+    fn is_typescript_parameter(s: &str) -> bool {
+        Self::is_typescript_identifier(s) && !TYPESCRIPT_RESERVED_WORDS.contains(&s) && s != "args"
+    }
+
+    /// `self` and `args` are left for the receiver and the rest parameter.
+    fn is_python_parameter(s: &str) -> bool {
+        Self::is_python_identifier(s) && s != "self" && s != "args"
+    }
+
+    fn typescript_parameters(signature: &Signature) -> String {
+        let arity = signature.get_arity();
+        let mut result: Vec<String> =
+            Self::parameter_names(signature, Self::is_typescript_parameter)
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| match i < arity.get_min() {
+                    true => format!("{name}: any"),
+                    false => format!("{name}?: any"),
+                })
+                .collect();
+        if arity.get_max().is_none() {
+            result.push(String::from("...args: any[]"));
+        }
+        result.join(", ")
+    }
+
+    fn typescript_members(members: &[(String, ValueType)], indent: usize) -> String {
+        if members.is_empty() {
+            return String::from("{}");
+        }
+        let padding = "    ".repeat(indent + 1);
+        let mut result = String::from("{\n");
+        for (name, value_type) in members {
+            let key = match Self::is_typescript_identifier(name) {
+                true => name.clone(),
+                false => Self::quote(name),
+            };
+            let member = match value_type {
+                ValueType::Function(signature) => {
+                    format!("{key}({}): any", Self::typescript_parameters(signature))
+                }
+                _ => format!("{key}: {}", Self::typescript_type(value_type, indent + 1)),
+            };
+            result.push_str(format!("{padding}{member};\n").as_str());
+        }
+        result.push_str("    ".repeat(indent).as_str());
+        result.push('}');
+        result
+    }
+
+    fn typescript_type(value_type: &ValueType, indent: usize) -> String {
+        match value_type {
+            ValueType::Number => String::from("number"),
+            ValueType::String => String::from("string"),
+            ValueType::Boolean => String::from("boolean"),
+            ValueType::Null => String::from("null"),
+            ValueType::Unknown => String::from("any"),
+            ValueType::Function(signature) => {
+                format!("({}) => any", Self::typescript_parameters(signature))
+            }
+            ValueType::Object(members) => Self::typescript_members(members, indent),
+            ValueType::Class {
+                constructor,
+                members,
+                ..
+            } => {
+                let parameters = match constructor {
+                    Some(signature) => Self::typescript_parameters(signature),
+                    None => String::new(),
+                };
+                format!(
+                    "new ({parameters}) => {}",
+                    Self::typescript_members(members, indent)
+                )
+            }
+        }
+    }
+
+    fn generate_typescript(types: &[(&String, &ValueType)]) -> String {
+        let mut result = String::from(
+            "// Declarations of the values exported to the polyglot bindings.\n\nexport interface PolyglotBindings {\n",
+        );
+        for (name, value_type) in types {
+            let value_type = Self::typescript_type(value_type, 1);
+            result.push_str(format!("    {}: {value_type};\n", Self::quote(name)).as_str());
+        }
+        result.push_str(
+            "}
+
+declare global {
+    const Polyglot: {
+        import<K extends keyof PolyglotBindings>(name: K): PolyglotBindings[K];
+        import(name: string): any;
+        export(name: string, value: any): void;
+        eval(language: string, source: string): any;
+        evalFile(language: string, path: string): any;
+    };
+}
+",
+        );
+        result
+    }
+
+    fn python_parameters(signature: &Signature) -> String {
+        let arity = signature.get_arity();
+        let mut result = vec![String::from("self")];
+        for (i, name) in Self::parameter_names(signature, Self::is_python_parameter)
+            .into_iter()
+            .enumerate()
+        {
+            match i < arity.get_min() {
+                true => result.push(format!("{name}: Any")),
+                false => result.push(format!("{name}: Any = ...")),
+            }
+        }
+        if arity.get_max().is_none() {
+            result.push(String::from("*args: Any"));
+        }
+        result.join(", ")
+    }
+
+    /// Python cannot express object shapes inline, so each of them is declared as a Protocol named after its path.
+    fn python_type(value_type: &ValueType, path: &str, classes: &mut PythonClasses) -> String {
+        match value_type {
+            ValueType::Number => String::from("float"),
+            ValueType::String => String::from("str"),
+            ValueType::Boolean => String::from("bool"),
+            ValueType::Null => String::from("None"),
+            ValueType::Unknown => String::from("Any"),
+            ValueType::Function(signature) => {
+                let class_name = classes.reserve(path);
+                classes.declarations.push(format!(
+                    "class {class_name}(Protocol):\n    def __call__({}) -> Any: ...\n",
+                    Self::python_parameters(signature)
+                ));
+                class_name
+            }
+            ValueType::Object(members) => {
+                let class_name = classes.reserve(path);
+                let body = Self::python_members(members, path, classes);
+                classes
+                    .declarations
+                    .push(format!("class {class_name}(Protocol):\n{body}"));
+                class_name
+            }
+            ValueType::Class {
+                constructor,
+                members,
+                ..
+            } => {
+                let class_name = classes.reserve(path);
+                let mut body = String::new();
+                if let Some(signature) = constructor {
+                    body.push_str(
+                        format!(
+                            "    def __init__({}) -> None: ...\n",
+                            Self::python_parameters(signature)
+                        )
+                        .as_str(),
+                    );
+                }
+                body.push_str(Self::python_members(members, path, classes).as_str());
+                classes
+                    .declarations
+                    .push(format!("class {class_name}:\n{body}"));
+                format!("type[{class_name}]")
+            }
+        }
+    }
+
+    fn python_members(
+        members: &[(String, ValueType)],
+        path: &str,
+        classes: &mut PythonClasses,
+    ) -> String {
+        let mut body = String::new();
+        for (name, value_type) in members
+            .iter()
+            .filter(|(n, _)| Self::is_python_identifier(n))
+        {
+            let member = match value_type {
+                ValueType::Function(signature) => format!(
+                    "    def {name}({}) -> Any: ...\n",
+                    Self::python_parameters(signature)
+                ),
+                _ => {
+                    let path = format!("{path}_{name}");
+                    format!(
+                        "    {name}: {}\n",
+                        Self::python_type(value_type, path.as_str(), classes)
+                    )
+                }
+            };
+            body.push_str(member.as_str());
+        }
+        if body.is_empty() {
+            body.push_str("    ...\n");
+        }
+        body
+    }
+
+    fn generate_python(types: &[(&String, &ValueType)]) -> String {
+        let mut classes = PythonClasses::default();
+        let mut overloads = String::new();
+        for (name, value_type) in types {
+            let value_type = Self::python_type(value_type, name, &mut classes);
+            overloads.push_str(
+                format!(
+                    "@overload\ndef import_value(name: Literal[{}]) -> {value_type}: ...\n",
+                    Self::quote(name)
+                )
+                .as_str(),
+            );
+        }
+
+        let mut result = String::from(
+            "# Stubs for the values exported to the polyglot bindings.\n\nfrom typing import Any, Literal, Protocol, overload\n\n",
+        );
+        for class in classes.declarations {
+            result.push_str(format!("{class}\n").as_str());
+        }
+        result.push_str(overloads.as_str());
+        result.push_str(
+            "@overload
+def import_value(name: str) -> Any: ...
+def export_value(name: str, value: Any) -> None: ...
+def eval(language: str, string: str) -> Any: ...
+",
+        );
+        result
+    }
+}
+
+impl PolygotProcessor for StubGenerator {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let mut inferrer = TypeInferrer::new();
+        inferrer.process(zip);
+
+        let mut types: Vec<(&String, &ValueType)> = inferrer.get_types().iter().collect();
+        types.sort_by_key(|(name, _)| *name);
+        self.typescript = Self::generate_typescript(types.as_slice());
+        self.python = Self::generate_python(types.as_slice());
+    }
+}
@@ -0,0 +1,236 @@
+use super::super::polyglot_zipper::NodeHandle;
+use super::super::polyglot_zipper::NodeKey;
+use super::super::util::{self, Language};
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A pattern matching taint sources or sinks written in one language.
+///
+/// Patterns are matched against the text of an expression, or the text of the callee for calls (everything before the arguments),
+/// and may use `*` as a wildcard, as in `req.body` or `*.exec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaintPattern {
+    language: Language,
+    pattern: String,
+}
+
+impl TaintPattern {
+    /// Initializes a new pattern for the given language.
+    pub fn new(language: Language, pattern: impl ToString) -> TaintPattern {
+        TaintPattern {
+            language,
+            pattern: pattern.to_string(),
+        }
+    }
+
+    fn matches(&self, language: &Language, text: &str) -> bool {
+        self.language == *language && util::glob_match(self.pattern.as_str(), text)
+    }
+}
+
+/// A flow of tainted data from a source to a sink, as found by the TaintAnalyzer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaintPath {
+    steps: Vec<NodeHandle>,
+}
+
+impl TaintPath {
+    /// Returns every node the tainted data flows through, from the source expression to the sink call.
+    pub fn get_steps(&self) -> &[NodeHandle] {
+        self.steps.as_slice()
+    }
+
+    /// Returns the expression the tainted data originates from.
+    pub fn get_source(&self) -> &NodeHandle {
+        &self.steps[0]
+    }
+
+    /// Returns the sink call the tainted data reaches.
+    pub fn get_sink(&self) -> &NodeHandle {
+        &self.steps[self.steps.len() - 1]
+    }
+}
+
+/// A processor that finds flows of data from configurable sources to configurable sinks, across languages.
+/// After processing a tree, use the `get_paths` method to retrieve the results.
+///
+/// Sources are expressions or calls matching a source pattern, and sinks are the arguments of calls matching a sink pattern.
+/// Taint flows through assignments, operators and other composite expressions, function arguments and return values,
+/// polyglot exports to imports of the same name, and eval calls to the value they return.
+/// Calls that cannot be resolved to a function definition are assumed to return tainted data if any argument is tainted.
+/// Like the CallGraphBuilder, each file or eval'd snippet is considered a single scope.
+pub struct TaintAnalyzer {
+    sources: Vec<TaintPattern>,
+    sinks: Vec<TaintPattern>,
+    paths: Vec<TaintPath>,
+}
+
+impl TaintAnalyzer {
+    /// Initializes a new TaintAnalyzer instance with the given source and sink patterns.
+    pub fn new(sources: Vec<TaintPattern>, sinks: Vec<TaintPattern>) -> TaintAnalyzer {
+        TaintAnalyzer {
+            sources,
+            sinks,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Returns the shortest path from each source to each sink it reaches in the last processed tree.
+    pub fn get_paths(&self) -> &[TaintPath] {
+        self.paths.as_slice()
+    }
+
+    /// Returns the text of the callee of a call, which is everything before its arguments.
+    fn callee_text<'a>(call: &PolyglotZipper<'a>) -> Option<&'a str> {
+        let arguments = call.child_by_field_name("arguments")?;
+        let length = arguments.byte_range().start - call.byte_range().start;
+        Some(call.code()[..length].trim_end())
+    }
+
+    fn is_source(&self, zip: &PolyglotZipper) -> bool {
+        let text = match zip.kind() {
+            "call" | "call_expression" | "method_invocation" => match Self::callee_text(zip) {
+                Some(t) => t,
+                None => return false,
+            },
+            "identifier"
+            | "attribute"
+            | "member_expression"
+            | "field_access"
+            | "subscript"
+            | "subscript_expression" => zip.code(),
+            _ => return false,
+        };
+        self.sources.iter().any(|s| s.matches(zip.get_lang(), text))
+    }
+
+    fn collect_sources<'a>(
+        &self,
+        zip: Option<PolyglotZipper<'a>>,
+        parent_is_source: bool,
+        sources: &mut Vec<PolyglotZipper<'a>>,
+    ) {
+        if let Some(zip) = zip {
+            // only the outermost expression is kept when nested expressions match, such as `req.body` in `req.body.x`
+            let is_source = self.is_source(&zip);
+            if is_source && !parent_is_source {
+                sources.push(zip.clone());
+            }
+            self.collect_sources(zip.child(0), is_source, sources);
+            self.collect_sources(zip.next_sibling(), parent_is_source, sources);
+        }
+    }
+
+    /// Returns true if taint flows from any child of the given node to the node itself.
+    fn is_composite(zip: &PolyglotZipper) -> bool {
+        matches!(
+            zip.kind(),
+            "binary_operator"
+                | "boolean_operator"
+                | "unary_operator"
+                | "binary_expression"
+                | "unary_expression"
+                | "parenthesized_expression"
+                | "conditional_expression"
+                | "ternary_expression"
+                | "string"
+                | "concatenated_string"
+                | "interpolation"
+                | "template_string"
+                | "template_substitution"
+                | "attribute"
+                | "member_expression"
+                | "field_access"
+                | "subscript"
+                | "subscript_expression"
+                | "array_access"
+                | "list"
+                | "tuple"
+                | "set"
+                | "dictionary"
+                | "pair"
+                | "array"
+                | "object"
+                | "array_initializer"
+                | "expression_list"
+                | "sequence_expression"
+                | "keyword_argument"
+                | "spread_element"
+                | "list_splat"
+                | "dictionary_splat"
+                | "await"
+                | "await_expression"
+                | "cast_expression"
+        )
+    }
+
+    fn find_paths<'a>(
+        &mut self,
+        source: &PolyglotZipper<'a>,
+        edges: &HashMap<NodeKey, Vec<PolyglotZipper<'a>>>,
+        sinks: &HashMap<NodeKey, PolyglotZipper<'a>>,
+    ) {
+        let mut predecessors: HashMap<NodeKey, PolyglotZipper<'a>> = HashMap::new();
+        let mut visited = HashSet::from([source.key()]);
+        let mut reached = HashSet::new();
+        let mut queue = VecDeque::from([source.clone()]);
+
+        while let Some(node) = queue.pop_front() {
+            if let Some(sink) = sinks.get(&node.key()) {
+                if reached.insert(sink.key()) {
+                    let mut steps = vec![sink.to_handle()];
+                    let mut current = Some(&node);
+                    while let Some(step) = current {
+                        steps.push(step.to_handle());
+                        current = predecessors.get(&step.key());
+                    }
+                    steps.reverse();
+                    self.paths.push(TaintPath { steps });
+                }
+            }
+
+            let composite = node.parent().filter(Self::is_composite);
+            let successors = edges
+                .get(&node.key())
+                .into_iter()
+                .flatten()
+                .chain(composite.iter());
+            for next in successors {
+                if visited.insert(next.key()) {
+                    predecessors.insert(next.key(), node.clone());
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+    }
+}
+
+impl PolygotProcessor for TaintAnalyzer {
+    fn process(&mut self, zip: PolyglotZipper) {
+        self.paths = Vec::new();
+
+        let mut sources = Vec::new();
+        self.collect_sources(Some(zip.clone()), false, &mut sources);
+
+        let index = ValueIndex::from(zip);
+        let mut sinks = HashMap::new();
+        for call in index.calls() {
+            let is_sink = Self::callee_text(&call.call).is_some_and(|t| {
+                self.sinks
+                    .iter()
+                    .any(|s| s.matches(call.call.get_lang(), t))
+            });
+            if is_sink {
+                for argument in call.arguments.iter() {
+                    sinks.insert(argument.key(), call.call.clone());
+                }
+            }
+        }
+
+        let edges = index.flow_edges();
+        for source in sources.iter() {
+            self.find_paths(source, &edges, &sinks);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use super::super::util::{InvalidArgumentError, Language};
+use super::arity::Arity;
+use super::value_flow::ValueIndex;
+use super::{PolyglotZipper, PolygotProcessor};
+use crate::polyglot_tree::polyglot_zipper::PolyglotBinding;
+use std::collections::HashMap;
+
+/// Values nested deeper than this, such as objects within objects, are not inferred.
+const MAX_DEPTH: usize = 8;
+
+/// The shape of a value shared between languages, as inferred from the code producing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    /// An integer or floating point number.
+    Number,
+    /// A string.
+    String,
+    /// A boolean.
+    Boolean,
+    /// `None`, `null` or `undefined`.
+    Null,
+    /// A function.
+    Function(Signature),
+    /// A class, with the signature of its constructor if it declares one, and the members of its instances.
+    Class {
+        name: Option<String>,
+        constructor: Option<Signature>,
+        members: Vec<(String, ValueType)>,
+    },
+    /// An object, dictionary or class instance, with its members in declaration order.
+    Object(Vec<(String, ValueType)>),
+    /// A value whose type could not be inferred, or that may have several types.
+    Unknown,
+}
+
+/// The parameters of a function or constructor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    arity: Arity,
+    parameters: Vec<String>,
+}
+
+impl Signature {
+    /// Computes the signature of a function definition, or returns None if the contained node is not one.
+    fn from(def: &PolyglotZipper) -> Option<Signature> {
+        let arity = Arity::from(def)?;
+        let mut parameters: Vec<String> = ValueIndex::parameters(def)
+            .iter()
+            .filter_map(ValueIndex::parameter_name)
+            .collect();
+        if Arity::is_python_method(def) && !parameters.is_empty() {
+            parameters.remove(0);
+        }
+        Some(Signature { arity, parameters })
+    }
+
+    /// Returns the number of arguments the function accepts.
+    pub fn get_arity(&self) -> &Arity {
+        &self.arity
+    }
+
+    /// Returns the names of the parameters in order, without the implicit `self` of Python methods.
+    /// Variadic parameters and parameters that are not plain names, such as destructuring patterns, are left out.
+    pub fn get_parameters(&self) -> &[String] {
+        self.parameters.as_slice()
+    }
+}
+
+impl ValueType {
+    /// Returns the type common to all the given types, or Unknown if they differ or there are none.
+    fn merge(types: impl IntoIterator<Item = ValueType>) -> ValueType {
+        let mut types = types.into_iter();
+        let first = match types.next() {
+            Some(t) => t,
+            None => return ValueType::Unknown,
+        };
+        match types.all(|t| t == first) {
+            true => first,
+            false => ValueType::Unknown,
+        }
+    }
+}
+
+/// Infers the type of an expression of the indexed tree.
+pub(super) fn infer_type<'a>(index: &ValueIndex<'a>, expr: &PolyglotZipper<'a>) -> ValueType {
+    infer(index, expr, 0)
+}
+
+/// Infers the type of an expression, following names, imports and eval calls to the values they may hold.
+fn infer<'a>(index: &ValueIndex<'a>, expr: &PolyglotZipper<'a>, depth: usize) -> ValueType {
+    if depth > MAX_DEPTH {
+        return ValueType::Unknown;
+    }
+    ValueType::merge(
+        index
+            .resolve(expr)
+            .iter()
+            .map(|r| infer_terminal(index, &r.value, depth)),
+    )
+}
+
+/// Infers the type of an expression that does not refer to another value.
+fn infer_terminal<'a>(
+    index: &ValueIndex<'a>,
+    expr: &PolyglotZipper<'a>,
+    depth: usize,
+) -> ValueType {
+    match expr.kind() {
+        "integer"
+        | "float"
+        | "number"
+        | "decimal_integer_literal"
+        | "hex_integer_literal"
+        | "octal_integer_literal"
+        | "binary_integer_literal"
+        | "decimal_floating_point_literal"
+        | "hex_floating_point_literal" => ValueType::Number,
+        "string" | "concatenated_string" | "template_string" | "string_literal" => {
+            ValueType::String
+        }
+        "true" | "false" => ValueType::Boolean,
+        "none" | "null" | "null_literal" | "undefined" => ValueType::Null,
+        "unary_operator" | "unary_expression" => match expr.named_children().into_iter().last() {
+            Some(operand) if infer(index, &operand, depth + 1) == ValueType::Number => {
+                ValueType::Number
+            }
+            _ => ValueType::Unknown,
+        },
+        "class_definition" | "class_declaration" | "class" => class_type(index, expr, depth),
+        "dictionary" | "object" => ValueType::Object(object_members(index, expr, depth)),
+        "call" | "new_expression" => instance_type(index, expr, depth),
+        _ => match Signature::from(expr) {
+            Some(signature) => ValueType::Function(signature),
+            None => ValueType::Unknown,
+        },
+    }
+}
+
+/// The type of the instances created by a Python call or a JavaScript `new` expression, if it creates one of a known class.
+fn instance_type<'a>(index: &ValueIndex<'a>, call: &PolyglotZipper<'a>, depth: usize) -> ValueType {
+    let constructor = match call.kind() {
+        "new_expression" => call.child_by_field_name("constructor"),
+        _ => call.child_by_field_name("function"),
+    };
+    let class = match constructor {
+        Some(c) => infer(index, &c, depth + 1),
+        None => return ValueType::Unknown,
+    };
+    match class {
+        ValueType::Class { members, .. } => ValueType::Object(members),
+        _ => ValueType::Unknown,
+    }
+}
+
+fn class_type<'a>(index: &ValueIndex<'a>, class: &PolyglotZipper<'a>, depth: usize) -> ValueType {
+    let body = match class.get_lang() {
+        Language::Java => class
+            .named_children()
+            .into_iter()
+            .find(|c| c.kind().eq("class_body")),
+        _ => class.child_by_field_name("body"),
+    };
+    let mut constructor = None;
+    let mut members = Vec::new();
+    for member in body.iter().flat_map(|b| b.named_children()) {
+        let member = match member.kind() {
+            "decorated_definition" => match member.child_by_field_name("definition") {
+                Some(definition) => definition,
+                None => continue,
+            },
+            "expression_statement" => match member.named_children().into_iter().next() {
+                Some(expression) => expression,
+                None => continue,
+            },
+            _ => member,
+        };
+        match member.kind() {
+            "function_definition" | "method_definition" | "method_declaration" => {
+                let name = match ValueIndex::definition_name(&member) {
+                    Some(n) => n,
+                    None => continue,
+                };
+                if matches!(name.as_str(), "__init__" | "constructor") {
+                    constructor = Signature::from(&member);
+                    for (name, value_type) in constructor_members(index, &member, depth) {
+                        add_member(&mut members, name, value_type);
+                    }
+                } else if let Some(function) = Signature::from(&member).map(ValueType::Function) {
+                    add_member(&mut members, name, function);
+                }
+            }
+            "constructor_declaration" => constructor = Signature::from(&member),
+            "assignment" => {
+                if let (Some(left), Some(right)) = (
+                    member.child_by_field_name("left"),
+                    member.child_by_field_name("right"),
+                ) {
+                    if left.kind().eq("identifier") {
+                        let value_type = infer(index, &right, depth + 1);
+                        add_member(&mut members, String::from(left.code()), value_type);
+                    }
+                }
+            }
+            "field_definition" => {
+                let name = member
+                    .child_by_field_name("property")
+                    .or_else(|| member.named_children().into_iter().next());
+                if let Some(name) = name {
+                    let value_type = match member.child_by_field_name("value") {
+                        Some(value) => infer(index, &value, depth + 1),
+                        None => ValueType::Unknown,
+                    };
+                    add_member(&mut members, String::from(name.code()), value_type);
+                }
+            }
+            "field_declaration" => {
+                let declared = java_declared_type(&member);
+                for declarator in member
+                    .named_children()
+                    .into_iter()
+                    .filter(|c| c.kind().eq("variable_declarator"))
+                {
+                    if let Some(name) = declarator.named_children().into_iter().next() {
+                        add_member(&mut members, String::from(name.code()), declared.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    ValueType::Class {
+        name: ValueIndex::definition_name(class),
+        constructor,
+        members,
+    }
+}
+
+/// Returns the attributes a constructor assigns to `self` or `this`, such as `self.x = 0`.
+fn constructor_members<'a>(
+    index: &ValueIndex<'a>,
+    constructor: &PolyglotZipper<'a>,
+    depth: usize,
+) -> Vec<(String, ValueType)> {
+    let mut members = Vec::new();
+    let mut pending: Vec<PolyglotZipper<'a>> = constructor.named_children();
+    while let Some(zip) = pending.pop() {
+        if zip.is_function_definition() {
+            continue;
+        }
+        if matches!(zip.kind(), "assignment" | "assignment_expression") {
+            let left = zip.child_by_field_name("left");
+            let right = zip.child_by_field_name("right");
+            if let (Some(left), Some(right)) = (left, right) {
+                let object = left.child_by_field_name("object");
+                let attribute = left
+                    .child_by_field_name("attribute")
+                    .or_else(|| left.child_by_field_name("property"));
+                if let (Some(object), Some(attribute)) = (object, attribute) {
+                    if matches!(object.code(), "self" | "this") {
+                        members.push((
+                            String::from(attribute.code()),
+                            infer(index, &right, depth + 1),
+                        ));
+                    }
+                }
+            }
+        }
+        pending.extend(zip.named_children().into_iter().rev());
+    }
+    members
+}
+
+/// The Java grammar names primitive types by kind, and class types are plain identifiers.
+/// The declared type is the first non-modifier child.
+fn java_declared_type(declaration: &PolyglotZipper) -> ValueType {
+    let declared = declaration
+        .named_children()
+        .into_iter()
+        .find(|c| !c.kind().eq("modifiers"));
+    match declared.as_ref().map(|d| (d.kind(), d.code())) {
+        Some(("integral_type" | "floating_point_type", _)) => ValueType::Number,
+        Some(("boolean_type", _)) => ValueType::Boolean,
+        Some(("identifier", "String")) => ValueType::String,
+        Some(("identifier", "Integer" | "Long" | "Double" | "Float" | "Short" | "Byte")) => {
+            ValueType::Number
+        }
+        Some(("identifier", "Boolean")) => ValueType::Boolean,
+        _ => ValueType::Unknown,
+    }
+}
+
+fn object_members<'a>(
+    index: &ValueIndex<'a>,
+    object: &PolyglotZipper<'a>,
+    depth: usize,
+) -> Vec<(String, ValueType)> {
+    let mut members = Vec::new();
+    for member in object.named_children() {
+        match member.kind() {
+            "pair" => {
+                let key = member.child_by_field_name("key");
+                let value = member.child_by_field_name("value");
+                if let (Some(key), Some(value)) = (key, value) {
+                    let name = match key.string_literal_value() {
+                        Some(name) => name,
+                        None if matches!(key.kind(), "identifier" | "number" | "integer") => {
+                            String::from(key.code())
+                        }
+                        None => continue,
+                    };
+                    add_member(&mut members, name, infer(index, &value, depth + 1));
+                }
+            }
+            // shorthand properties, such as `{ x }`
+            "identifier" => {
+                let value_type = infer(index, &member, depth + 1);
+                add_member(&mut members, String::from(member.code()), value_type);
+            }
+            "method_definition" => {
+                let name = member.child_by_field_name("name");
+                if let (Some(name), Some(function)) =
+                    (name, Signature::from(&member).map(ValueType::Function))
+                {
+                    add_member(&mut members, String::from(name.code()), function);
+                }
+            }
+            _ => {}
+        }
+    }
+    members
+}
+
+/// Members defined several times keep their first position and their last type.
+fn add_member(members: &mut Vec<(String, ValueType)>, name: String, value_type: ValueType) {
+    match members.iter_mut().find(|(n, _)| *n == name) {
+        Some(member) => member.1 = value_type,
+        None => members.push((name, value_type)),
+    }
+}
+
+/// A binding shared between languages, with the inferred type of the value shared through it.
+pub struct TypedBinding<'a> {
+    binding: PolyglotBinding<'a>,
+    value_type: Option<ValueType>,
+}
+
+impl<'a> TypedBinding<'a> {
+    /// Returns the binding itself.
+    pub fn binding(&self) -> &PolyglotBinding<'a> {
+        &self.binding
+    }
+
+    /// Returns the name of the binding, with escape sequences decoded.
+    pub fn name(&self) -> &str {
+        self.binding.name()
+    }
+
+    /// Returns the inferred type of the value shared through this binding, or None if it is never exported.
+    pub fn value_type(&self) -> Option<&ValueType> {
+        self.value_type.as_ref()
+    }
+}
+
+/// A processor that infers the type of every exported value of a polyglot tree.
+/// After processing a tree, use the `get_types` or `get_type` methods to retrieve the results,
+/// or `get_binding` to attach them to the binding of an import or export call.
+///
+/// Types are inferred from literals, function and class definitions, dictionaries and objects, and class instances.
+/// Exported names are followed to the values they are assigned, imports to the values exported under the same name,
+/// and eval calls to the last expression of the code they evaluate.
+/// When a binding is exported several times with different types, its type is Unknown.
+pub struct TypeInferrer {
+    types: HashMap<String, ValueType>,
+}
+
+impl Default for TypeInferrer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeInferrer {
+    /// Initializes a new TypeInferrer instance.
+    pub fn new() -> Self {
+        TypeInferrer {
+            types: HashMap::new(),
+        }
+    }
+
+    /// Returns the type of every binding exported in the last processed tree, by binding name.
+    pub fn get_types(&self) -> &HashMap<String, ValueType> {
+        &self.types
+    }
+
+    /// Returns the type of the binding exported under the given name, or None if it is never exported.
+    pub fn get_type(&self, name: &str) -> Option<&ValueType> {
+        self.types.get(name)
+    }
+
+    /// Get the binding an import or export call refers to, with the type of the value exported under its name
+    /// in the last processed tree, so that import sites know the shape of the value they receive.
+    ///
+    /// Returns an InvalidArgumentError if the contained node is not an import or export call,
+    /// or if the binding name is not a string literal.
+    pub fn get_binding<'a>(
+        &self,
+        zip: &PolyglotZipper<'a>,
+    ) -> Result<TypedBinding<'a>, InvalidArgumentError> {
+        let binding = zip.get_binding()?;
+        let value_type = self.get_type(binding.name()).cloned();
+        Ok(TypedBinding {
+            binding,
+            value_type,
+        })
+    }
+}
+
+impl PolygotProcessor for TypeInferrer {
+    fn process(&mut self, zip: PolyglotZipper) {
+        let index = ValueIndex::from(zip);
+
+        let mut types: HashMap<String, Vec<ValueType>> = HashMap::new();
+        for (name, value) in index.all_exports() {
+            types
+                .entry(String::from(name))
+                .or_default()
+                .push(infer(&index, value, 0));
+        }
+        self.types = types
+            .into_iter()
+            .map(|(name, types)| (name, ValueType::merge(types)))
+            .collect();
+    }
+}
@@ -0,0 +1,618 @@
+use super::super::polyglot_zipper::NodeKey;
+use super::super::util::Language;
+use super::super::PolyglotTree;
+use super::PolyglotZipper;
+use std::collections::{HashMap, HashSet};
+
+/// Trees are compared by address, since each of them is a distinct unit of code with its own names.
+type TreeKey = *const PolyglotTree;
+
+/// A call found in the tree, with the expression being called and the function definition the call appears in.
+///
+/// For Java hosts, calls to `Value.execute` and `Value.executeVoid` are recorded with the receiver as the callee,
+/// and calls to methods of any other object have no callee, since their target cannot be resolved.
+pub(super) struct CallSite<'a> {
+    pub call: PolyglotZipper<'a>,
+    pub callee: Option<PolyglotZipper<'a>>,
+    pub receiver: Option<PolyglotZipper<'a>>,
+    pub arguments: Vec<PolyglotZipper<'a>>,
+    pub caller: Option<PolyglotZipper<'a>>,
+}
+
+/// An expression a value may originate from, along with whether reaching it required crossing a polyglot boundary.
+pub(super) struct ResolvedValue<'a> {
+    pub value: PolyglotZipper<'a>,
+    pub polyglot: bool,
+}
+
+/// An index of the names, exports, function definitions and calls of a polyglot tree,
+/// used to resolve which expressions a value may originate from across languages.
+///
+/// The resolution is flow-insensitive, and each tree (i.e. each file or eval'd snippet) is considered a single scope.
+/// A tree shared by several eval calls, such as a file evaluated from several places, is only indexed once.
+pub(super) struct ValueIndex<'a> {
+    trees: HashSet<TreeKey>,
+    bindings: HashMap<(TreeKey, String), Vec<PolyglotZipper<'a>>>,
+    uses: HashMap<(TreeKey, String), Vec<PolyglotZipper<'a>>>,
+    python_imports: HashMap<(TreeKey, String), String>,
+    exports: HashMap<String, Vec<PolyglotZipper<'a>>>,
+    imports: HashMap<String, Vec<PolyglotZipper<'a>>>,
+    evals: Vec<PolyglotZipper<'a>>,
+    functions: Vec<PolyglotZipper<'a>>,
+    returns: HashMap<NodeKey, Vec<PolyglotZipper<'a>>>,
+    calls: Vec<CallSite<'a>>,
+}
+
+impl<'a> ValueIndex<'a> {
+    /// Builds the index for the tree, starting from the given zipper.
+    pub fn from(zip: PolyglotZipper<'a>) -> ValueIndex<'a> {
+        let mut index = ValueIndex {
+            trees: HashSet::from([zip.tree() as TreeKey]),
+            bindings: HashMap::new(),
+            uses: HashMap::new(),
+            python_imports: HashMap::new(),
+            exports: HashMap::new(),
+            imports: HashMap::new(),
+            evals: Vec::new(),
+            functions: Vec::new(),
+            returns: HashMap::new(),
+            calls: Vec::new(),
+        };
+        index.index_impl(Some(zip), None);
+        index
+    }
+
+    /// Returns every function definition of the tree, in traversal order.
+    pub fn functions(&self) -> &[PolyglotZipper<'a>] {
+        self.functions.as_slice()
+    }
+
+    /// Returns every call of the tree, in traversal order.
+    pub fn calls(&self) -> &[CallSite<'a>] {
+        self.calls.as_slice()
+    }
+
+    /// Returns every eval call of the tree, in traversal order.
+    pub fn evals(&self) -> &[PolyglotZipper<'a>] {
+        self.evals.as_slice()
+    }
+
+    /// Returns the expressions whose value the given function definition may return.
+    /// For lambdas and arrow functions with an expression body, this is the body itself.
+    pub fn returns(&self, def: &PolyglotZipper<'a>) -> &[PolyglotZipper<'a>] {
+        self.returns.get(&def.key()).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns every name bound in the tree, with the values bound to it.
+    /// Values always belong to the tree their name is bound in.
+    pub fn all_bindings(&self) -> impl Iterator<Item = (&str, &[PolyglotZipper<'a>])> + '_ {
+        self.bindings
+            .iter()
+            .map(|((_, name), values)| (name.as_str(), values.as_slice()))
+    }
+
+    /// Returns every identifier with the given name in the tree of the given zipper, in traversal order.
+    pub fn uses(&self, zip: &PolyglotZipper<'a>, name: &str) -> &[PolyglotZipper<'a>] {
+        self.uses
+            .get(&(zip.tree() as TreeKey, String::from(name)))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns the dotted name of an expression with its leading name resolved through the Python imports of its tree,
+    /// such as `subprocess.run` for `run` after `from subprocess import run`, or `os.system` for `o.system` after `import os as o`.
+    /// Names that are not imported, and expressions in other languages, are returned as written.
+    pub fn qualified_name(&self, expr: &PolyglotZipper<'a>) -> String {
+        let code = expr.code();
+        let (head, rest) = match code.find('.') {
+            Some(i) => code.split_at(i),
+            None => (code, ""),
+        };
+        match self
+            .python_imports
+            .get(&(expr.tree() as TreeKey, String::from(head)))
+        {
+            Some(module_member) => format!("{module_member}{rest}"),
+            None => String::from(code),
+        }
+    }
+
+    /// Returns every export of the tree, with the binding name and exported value.
+    pub fn all_exports(&self) -> impl Iterator<Item = (&str, &PolyglotZipper<'a>)> + '_ {
+        self.exports
+            .iter()
+            .flat_map(|(name, values)| values.iter().map(move |v| (name.as_str(), v)))
+    }
+
+    /// Returns the import calls of the given binding name, in any language.
+    pub fn imports(&self, name: &str) -> &[PolyglotZipper<'a>] {
+        self.imports.get(name).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns the values bound to the given name in the tree of the given zipper.
+    pub fn bindings(&self, zip: &PolyglotZipper<'a>, name: &str) -> &[PolyglotZipper<'a>] {
+        self.bindings
+            .get(&(zip.tree() as TreeKey, String::from(name)))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns the values exported under the given binding name, in any language.
+    pub fn exports(&self, name: &str) -> &[PolyglotZipper<'a>] {
+        self.exports.get(name).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Builds the edges of the data flow graph, from each expression to the expressions its value flows to,
+    /// keyed by the expression the value flows from.
+    pub fn flow_edges(&self) -> HashMap<NodeKey, Vec<PolyglotZipper<'a>>> {
+        let mut edges: HashMap<NodeKey, Vec<PolyglotZipper<'a>>> = HashMap::new();
+        for (from, to) in self.flow_edge_list() {
+            edges.entry(from.key()).or_default().push(to);
+        }
+        edges
+    }
+
+    /// Builds the edges of the data flow graph as pairs of the expression a value flows from and the expression it flows to.
+    ///
+    /// Values flow from assignments to the uses of the assigned name, from exports to the imports of the same name,
+    /// from the evaluated code to its eval call, and from arguments to parameters and returned values to calls.
+    /// Calls that cannot be resolved to a function definition receive the values of their arguments and receiver.
+    /// Flows from an expression to its enclosing expression are left to the caller.
+    pub fn flow_edge_list(&self) -> Vec<(PolyglotZipper<'a>, PolyglotZipper<'a>)> {
+        let mut edges = Vec::new();
+        let mut add = |from: &PolyglotZipper<'a>, to: &PolyglotZipper<'a>| {
+            edges.push((from.clone(), to.clone()))
+        };
+
+        for (name, values) in self.all_bindings() {
+            for value in values {
+                for used in self.uses(value, name) {
+                    add(value, used);
+                }
+            }
+        }
+
+        for (name, value) in self.all_exports() {
+            for import in self.imports(name) {
+                add(value, import);
+            }
+        }
+
+        for eval in self.evals() {
+            if let Some(result) = Self::eval_result(eval) {
+                add(&result, eval);
+            }
+        }
+
+        for call in self.calls() {
+            let targets: Vec<PolyglotZipper<'a>> = call
+                .callee
+                .iter()
+                .flat_map(|c| self.resolve(c))
+                .map(|t| t.value)
+                .filter(|t| t.is_function_definition())
+                .collect();
+
+            if targets.is_empty() {
+                for argument in call.arguments.iter().chain(call.receiver.iter()) {
+                    add(argument, &call.call);
+                }
+                continue;
+            }
+
+            for target in targets.iter() {
+                let parameters = Self::parameters(target);
+                let mut position = 0;
+                for argument in call.arguments.iter() {
+                    let (name, value) = if argument.kind().eq("keyword_argument") {
+                        match (
+                            argument.child_by_field_name("name"),
+                            argument.child_by_field_name("value"),
+                        ) {
+                            (Some(n), Some(v)) => (Some(String::from(n.code())), v),
+                            _ => continue,
+                        }
+                    } else {
+                        position += 1;
+                        let name = parameters.get(position - 1).and_then(Self::parameter_name);
+                        (name, argument.clone())
+                    };
+                    if let Some(name) = name {
+                        for used in self.uses(target, name.as_str()) {
+                            add(&value, used);
+                        }
+                    }
+                }
+                for returned in self.returns(target) {
+                    add(returned, &call.call);
+                }
+            }
+        }
+        edges
+    }
+
+    /// Returns the expressions the value of `expr` may originate from.
+    ///
+    /// Names are followed to the values they are bound to, imports to the values exported under the same name,
+    /// and eval calls to the last expression of the evaluated code.
+    /// Any other expression is its own origin; names that are never bound have none.
+    pub fn resolve(&self, expr: &PolyglotZipper<'a>) -> Vec<ResolvedValue<'a>> {
+        let mut result = Vec::new();
+        self.resolve_impl(expr.clone(), false, &mut HashSet::new(), &mut result);
+        result
+    }
+
+    fn resolve_impl(
+        &self,
+        expr: PolyglotZipper<'a>,
+        polyglot: bool,
+        visited: &mut HashSet<NodeKey>,
+        result: &mut Vec<ResolvedValue<'a>>,
+    ) {
+        if !visited.insert(expr.key()) {
+            return;
+        }
+
+        if expr.is_polyglot_import_call() {
+            if let Ok(name) = expr.get_binding_name() {
+                for value in self.exports(name.as_str()) {
+                    self.resolve_impl(value.clone(), true, visited, result);
+                }
+            }
+        } else if expr.is_polyglot_eval_call() {
+            if let Some(value) = Self::eval_result(&expr) {
+                self.resolve_impl(value, true, visited, result);
+            }
+        } else if expr.kind().eq("identifier") {
+            for value in self.bindings(&expr, expr.code()) {
+                self.resolve_impl(value.clone(), polyglot, visited, result);
+            }
+        } else if expr.kind().eq("parenthesized_expression") {
+            if let Some(inner) = expr.named_children().into_iter().next() {
+                self.resolve_impl(inner, polyglot, visited, result);
+            }
+        } else {
+            result.push(ResolvedValue {
+                value: expr,
+                polyglot,
+            });
+        }
+    }
+
+    /// Returns the expression whose value an eval call returns, which is the last expression statement of the evaluated code.
+    pub fn eval_result(eval: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+        let root = eval.child(0)?;
+        let last = root
+            .named_children()
+            .into_iter()
+            .rev()
+            .find(|c| !c.kind().eq("comment"))?;
+        if !last.kind().eq("expression_statement") {
+            return None;
+        }
+        last.named_children().into_iter().next()
+    }
+
+    /// Returns the name a function or class definition is bound to, if any.
+    /// Anonymous functions are named after the variable they are directly assigned to.
+    pub fn definition_name(def: &PolyglotZipper<'a>) -> Option<String> {
+        if let Some(name) = Self::declared_name(def) {
+            return Some(name);
+        }
+        let parent = def.parent()?;
+        let name = match parent.kind() {
+            "variable_declarator" => match def.get_lang() {
+                Language::Java => Self::first_identifier(&parent)?,
+                _ => parent.child_by_field_name("name")?,
+            },
+            "assignment" | "assignment_expression" => parent.child_by_field_name("left")?,
+            "pair" => parent.child_by_field_name("key")?,
+            _ => return None,
+        };
+        match name.kind() {
+            "identifier" => Some(String::from(name.code())),
+            _ => None,
+        }
+    }
+
+    /// Returns the name a definition declares for itself, ignoring what it may be assigned to.
+    fn declared_name(def: &PolyglotZipper<'a>) -> Option<String> {
+        let name = match (def.get_lang(), def.kind()) {
+            // type names are identifiers as well, so methods are named by the identifier before their parameters
+            (Language::Java, "method_declaration" | "constructor_declaration") => {
+                let children = def.named_children();
+                let parameters = children
+                    .iter()
+                    .position(|c| c.kind().eq("formal_parameters"))?;
+                children[..parameters]
+                    .iter()
+                    .rev()
+                    .find(|c| c.kind().eq("identifier"))?
+                    .clone()
+            }
+            (Language::Java, "class_declaration" | "interface_declaration") => {
+                Self::first_identifier(def)?
+            }
+            (
+                Language::Python | Language::JavaScript,
+                "function_definition"
+                | "class_definition"
+                | "function_declaration"
+                | "generator_function_declaration"
+                | "method_definition"
+                | "class_declaration",
+            ) => def.child_by_field_name("name")?,
+            _ => return None,
+        };
+        Some(String::from(name.code()))
+    }
+
+    /// Returns the parameter nodes of a function definition, in order.
+    pub fn parameters(def: &PolyglotZipper<'a>) -> Vec<PolyglotZipper<'a>> {
+        let parameters = match def.get_lang() {
+            Language::Java => def.named_children().into_iter().find(|c| {
+                matches!(
+                    c.kind(),
+                    "formal_parameters" | "inferred_parameters" | "identifier"
+                )
+            }),
+            _ => def
+                .child_by_field_name("parameters")
+                .or_else(|| def.child_by_field_name("parameter")),
+        };
+        match parameters {
+            Some(p) if p.kind().eq("identifier") => vec![p], // single parameter lambdas
+            Some(p) => p
+                .named_children()
+                .into_iter()
+                .filter(|c| !c.kind().eq("comment"))
+                .collect(),
+            None => Vec::new(), // Python lambdas without parameters
+        }
+    }
+
+    /// Returns the name a parameter binds, or None for separators and variadic parameters.
+    pub fn parameter_name(parameter: &PolyglotZipper<'a>) -> Option<String> {
+        let name = match parameter.kind() {
+            "identifier" => parameter.clone(),
+            "default_parameter" | "typed_default_parameter" => {
+                parameter.child_by_field_name("name")?
+            }
+            "assignment_pattern" => parameter.child_by_field_name("left")?,
+            "typed_parameter" => Self::first_identifier(parameter)?,
+            "formal_parameter" => parameter
+                .named_children()
+                .into_iter()
+                .rev()
+                .find(|c| c.kind().eq("identifier"))?,
+            _ => return None,
+        };
+        match name.kind() {
+            "identifier" => Some(String::from(name.code())),
+            _ => None,
+        }
+    }
+
+    /// Returns the expression a lambda or arrow function returns, if its body is an expression rather than a block.
+    fn expression_body(def: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+        let body = match (def.get_lang(), def.kind()) {
+            (Language::Python, "lambda") | (Language::JavaScript, "arrow_function") => {
+                def.child_by_field_name("body")?
+            }
+            (Language::Java, "lambda_expression") => def.named_children().into_iter().last()?,
+            _ => return None,
+        };
+        match body.kind() {
+            "block" | "statement_block" => None,
+            _ => Some(body),
+        }
+    }
+
+    /// The Java grammar does not reliably expose field names, so declarations are named after their first identifier.
+    fn first_identifier(zip: &PolyglotZipper<'a>) -> Option<PolyglotZipper<'a>> {
+        zip.named_children()
+            .into_iter()
+            .find(|c| c.kind().eq("identifier"))
+    }
+
+    /// Returns the name and value of an assignment or variable declaration, if it assigns to a plain name.
+    fn assignment(zip: &PolyglotZipper<'a>) -> Option<(PolyglotZipper<'a>, PolyglotZipper<'a>)> {
+        let (name, value) = match (zip.get_lang(), zip.kind()) {
+            (Language::Python, "assignment") | (Language::JavaScript, "assignment_expression") => (
+                zip.child_by_field_name("left")?,
+                zip.child_by_field_name("right")?,
+            ),
+            (Language::JavaScript, "variable_declarator") => (
+                zip.child_by_field_name("name")?,
+                zip.child_by_field_name("value")?,
+            ),
+            (Language::Java, "variable_declarator") => {
+                let children = zip.named_children();
+                if children.len() < 2 {
+                    return None;
+                }
+                (children.first()?.clone(), children.last()?.clone())
+            }
+            // try-with-resources declarations, whose type comes before the name
+            (Language::Java, "resource") => {
+                let children = zip.named_children();
+                if children.len() < 3 {
+                    return None;
+                }
+                (
+                    children[children.len() - 2].clone(),
+                    children.last()?.clone(),
+                )
+            }
+            _ => return None,
+        };
+        match name.kind() {
+            "identifier" => Some((name, value)),
+            _ => None,
+        }
+    }
+
+    /// Returns the names a Python import statement binds, with the dotted name of the module or module member bound to each.
+    fn imported_names(zip: &PolyglotZipper<'a>) -> Vec<(String, String)> {
+        let module = match (zip.get_lang(), zip.kind()) {
+            (Language::Python, "import_statement") => None,
+            (Language::Python, "import_from_statement") => {
+                match zip.child_by_field_name("module_name") {
+                    Some(module) => Some(String::from(module.code())),
+                    None => return Vec::new(),
+                }
+            }
+            _ => return Vec::new(),
+        };
+        let mut names = Vec::new();
+        // the module of a from-import is a dotted name as well, and comes first
+        let skip = usize::from(module.is_some());
+        for name in zip.named_children().into_iter().skip(skip) {
+            let (imported, alias) = match name.kind() {
+                "dotted_name" => (String::from(name.code()), None),
+                "aliased_import" => match (
+                    name.child_by_field_name("name"),
+                    name.child_by_field_name("alias"),
+                ) {
+                    (Some(imported), Some(alias)) => (
+                        String::from(imported.code()),
+                        Some(String::from(alias.code())),
+                    ),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            names.push(match (&module, alias) {
+                (Some(module), alias) => (
+                    alias.unwrap_or_else(|| imported.clone()),
+                    format!("{module}.{imported}"),
+                ),
+                (None, Some(alias)) => (alias, imported),
+                // `import a.b` binds `a`, not `a.b`
+                (None, None) => {
+                    let package = imported.split('.').next().unwrap_or_default();
+                    (String::from(package), String::from(package))
+                }
+            });
+        }
+        names
+    }
+
+    /// Returns the call site of a call, if the contained node is one.
+    fn call(zip: &PolyglotZipper<'a>, caller: Option<PolyglotZipper<'a>>) -> Option<CallSite<'a>> {
+        if zip.is_polyglot_eval_call()
+            || zip.is_polyglot_import_call()
+            || zip.is_polyglot_export_call()
+        {
+            return None;
+        }
+        let (callee, receiver) = match (zip.get_lang(), zip.kind()) {
+            (Language::Python, "call") | (Language::JavaScript, "call_expression") => {
+                let function = zip.child_by_field_name("function")?;
+                let receiver = function.child_by_field_name("object");
+                (Some(function), receiver)
+            }
+            (Language::Java, "method_invocation") => {
+                let name = zip.child_by_field_name("name")?;
+                match zip.child_by_field_name("object") {
+                    Some(object) if matches!(name.code(), "execute" | "executeVoid") => {
+                        (Some(object), None)
+                    }
+                    Some(object) if object.kind().eq("this") => (Some(name), None),
+                    Some(object) => (None, Some(object)),
+                    None => (Some(name), None),
+                }
+            }
+            _ => return None,
+        };
+        let arguments = zip
+            .child_by_field_name("arguments")?
+            .named_children()
+            .into_iter()
+            .filter(|a| !a.kind().eq("comment"))
+            .collect();
+        Some(CallSite {
+            call: zip.clone(),
+            callee,
+            receiver,
+            arguments,
+            caller,
+        })
+    }
+
+    fn bind(&mut self, name: &PolyglotZipper<'a>, value: PolyglotZipper<'a>) {
+        self.bind_name(name, String::from(name.code()), value)
+    }
+
+    fn bind_name(&mut self, zip: &PolyglotZipper<'a>, name: String, value: PolyglotZipper<'a>) {
+        self.bindings
+            .entry((zip.tree() as TreeKey, name))
+            .or_default()
+            .push(value);
+    }
+
+    fn index_impl(&mut self, zip: Option<PolyglotZipper<'a>>, caller: Option<PolyglotZipper<'a>>) {
+        if let Some(zip) = zip {
+            if zip.is_function_definition() {
+                self.functions.push(zip.clone());
+            }
+            if let Some(name) = Self::declared_name(&zip) {
+                self.bind_name(&zip, name, zip.clone());
+            }
+            if let Some((name, value)) = Self::assignment(&zip) {
+                self.bind(&name, value);
+            }
+            for (name, module_member) in Self::imported_names(&zip) {
+                self.python_imports
+                    .insert((zip.tree() as TreeKey, name), module_member);
+            }
+            if zip.kind().eq("identifier") {
+                self.uses
+                    .entry((zip.tree() as TreeKey, String::from(zip.code())))
+                    .or_default()
+                    .push(zip.clone());
+            }
+            if zip.is_polyglot_export_call() {
+                if let Ok(binding) = zip.get_binding() {
+                    if let Some(value) = binding.value() {
+                        self.exports
+                            .entry(String::from(binding.name()))
+                            .or_default()
+                            .push(value.clone());
+                    }
+                }
+            } else if zip.is_polyglot_import_call() {
+                if let Ok(name) = zip.get_binding_name() {
+                    self.imports.entry(name).or_default().push(zip.clone());
+                }
+            } else if zip.is_polyglot_eval_call() {
+                self.evals.push(zip.clone());
+            }
+            if let Some(call) = Self::call(&zip, caller.clone()) {
+                self.calls.push(call);
+            }
+            if let Some(function) = caller.as_ref() {
+                if zip.kind().eq("return_statement") {
+                    if let Some(value) = zip.named_children().into_iter().next() {
+                        self.returns.entry(function.key()).or_default().push(value);
+                    }
+                }
+            }
+            if let Some(body) = Self::expression_body(&zip) {
+                self.returns.entry(zip.key()).or_default().push(body);
+            }
+
+            let child_caller = match zip.is_function_definition() {
+                true => Some(zip.clone()),
+                false => caller.clone(),
+            };
+            let child = zip.child(0);
+            let is_indexed = zip.is_polyglot_eval_call()
+                && child
+                    .as_ref()
+                    .is_some_and(|c| !self.trees.insert(c.tree() as TreeKey));
+            if !is_indexed {
+                self.index_impl(child, child_caller);
+            }
+            self.index_impl(zip.next_sibling(), caller);
+        }
+    }
+}
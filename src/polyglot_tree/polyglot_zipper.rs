@@ -1,38 +1,240 @@
-use tree_sitter::{Node, TreeCursor};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Point, TreeCursor};
 
-use super::util::{InvalidArgumentError, Language};
+use super::util::{InvalidArgumentError, Language, PositionEncoding};
 
 use super::PolyglotTree;
 
 /// A PolyglotZipper is an object based on a PolyglotTree, which contains one of the tree's nodes.
 /// Zippers allow navigation of the tree and retrieval of node properties for analysis tasks.
+#[derive(Clone)]
 pub struct PolyglotZipper<'a> {
     tree: &'a PolyglotTree,
     node: TreeCursor<'a>,
 }
 
-impl PolyglotZipper<'_> {
+/// Identifies the node a zipper contains by the address of its tree and the id of the node, to key maps and sets of nodes with.
+///
+/// Zippers reference their tree, whose lazily computed parts make them unfit as keys themselves.
+pub(crate) type NodeKey = (*const PolyglotTree, usize);
+
+/// Two zippers are equal if they contain the same node of the same tree, regardless of how they got there.
+impl PartialEq for PolyglotZipper<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for PolyglotZipper<'_> {}
+
+impl Hash for PolyglotZipper<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+/// An owned handle to a node of a polyglot tree.
+///
+/// Unlike zippers, handles do not borrow the tree, which makes them suitable for storing the results of an analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+    file: Option<PathBuf>,
+    language: Language,
+    kind: String,
+    code: String,
+    start_byte: usize,
+    end_byte: usize,
+    start_position: Point,
+    end_position: Point,
+    host_byte_range: std::ops::Range<usize>,
+    host_position: Point,
+}
+
+impl NodeHandle {
+    /// Returns the file the node physically appears in, or None if the tree was built from a code snippet.
+    pub fn get_file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Returns the language the node is written in.
+    pub fn get_lang(&self) -> Language {
+        self.language
+    }
+
+    /// Returns the node's type, as given by `PolyglotZipper::kind`.
+    pub fn kind(&self) -> &str {
+        self.kind.as_str()
+    }
+
+    /// Returns the node's source code.
+    pub fn code(&self) -> &str {
+        self.code.as_str()
+    }
+
+    /// Returns the byte range of the node.
+    pub fn byte_range(&self) -> std::ops::Range<usize> {
+        self.start_byte..self.end_byte
+    }
+
+    /// Returns the node's start position in terms of rows and columns.
+    pub fn start_position(&self) -> Point {
+        self.start_position
+    }
+
+    /// Returns the node's end position in terms of rows and columns.
+    pub fn end_position(&self) -> Point {
+        self.end_position
+    }
+
+    /// Returns the byte range of the node in the file it physically appears in, as given by `PolyglotZipper::host_byte_range`.
+    pub fn host_byte_range(&self) -> std::ops::Range<usize> {
+        self.host_byte_range.clone()
+    }
+
+    /// Returns the node's start position in the file it physically appears in, as given by `PolyglotZipper::host_position`.
+    pub fn host_position(&self) -> Point {
+        self.host_position
+    }
+
+    /// Internal function to check whether this is a handle to the node contained in the given zipper,
+    /// without building a new handle.
+    /// Host ranges are compared as well, since nodes of different snippets of a file may have the same range in their snippet.
+    pub(crate) fn is_handle_of(&self, zip: &PolyglotZipper) -> bool {
+        self.byte_range() == zip.byte_range()
+            && self.host_byte_range == zip.host_byte_range()
+            && self.kind.eq(zip.kind())
+            && self.language == *zip.get_lang()
+            && self.get_file() == zip.get_file()
+    }
+}
+
+/// A binding shared between languages through an import or export call.
+pub struct PolyglotBinding<'a> {
+    name: String,
+    value: Option<PolyglotZipper<'a>>,
+}
+
+/// The arguments of an eval call, as written at the call site.
+pub(crate) struct EvalArguments<'a> {
+    /// The language argument, which Java calls evaluating a `Source` object do not have.
+    pub language: Option<PolyglotZipper<'a>>,
+    /// The code argument, or the path argument for calls evaluating a file.
+    pub source: Option<PolyglotZipper<'a>>,
+    pub is_file: bool,
+}
+
+impl<'a> PolyglotBinding<'a> {
+    /// Returns the name of the binding, with escape sequences decoded.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns a zipper located at the exported value expression, or None for import calls.
+    ///
+    /// For decorated Python definitions, this is the definition itself.
+    pub fn value(&self) -> Option<&PolyglotZipper<'a>> {
+        self.value.as_ref()
+    }
+}
+
+impl<'a> PolyglotZipper<'a> {
     /// Returns a new zipper for the given tree, located at the root.
-    pub fn from(tree: &'_ PolyglotTree) -> PolyglotZipper<'_> {
+    pub fn from(tree: &'a PolyglotTree) -> PolyglotZipper<'a> {
         Self::from_impl(tree, tree.root_node())
     }
 
-    fn from_impl<'a>(tree: &'a PolyglotTree, node: Node<'a>) -> PolyglotZipper<'a> {
+    pub(super) fn from_impl(tree: &'a PolyglotTree, node: Node<'a>) -> PolyglotZipper<'a> {
         PolyglotZipper {
             tree,
             node: node.walk(),
         }
     }
 
-    fn node(&self) -> Node {
+    fn node(&self) -> Node<'a> {
         self.node.node()
     }
 
+    /// Returns an owned handle to the contained node.
+    pub fn to_handle(&self) -> NodeHandle {
+        NodeHandle {
+            file: self.get_file().map(PathBuf::from),
+            language: *self.get_lang(),
+            kind: String::from(self.kind()),
+            code: String::from(self.code()),
+            start_byte: self.byte_range().start,
+            end_byte: self.byte_range().end,
+            start_position: self.start_position(),
+            end_position: self.end_position(),
+            host_byte_range: self.host_byte_range(),
+            host_position: self.host_position(),
+        }
+    }
+
+    /// Internal function to get the key identifying the contained node.
+    pub(crate) fn key(&self) -> NodeKey {
+        (self.tree, self.node().id())
+    }
+
+    /// Internal function to get the tree the contained node belongs to, which identifies the code unit it was parsed from.
+    pub(crate) fn tree(&self) -> &'a PolyglotTree {
+        self.tree
+    }
+
+    /// Internal function to get the zipper for the child with the given field name.
+    /// Unlike `child`, this never jumps to the subtree of an eval call.
+    pub(crate) fn child_by_field_name(&self, name: &str) -> Option<PolyglotZipper<'a>> {
+        Some(Self::from_impl(
+            self.tree,
+            self.node().child_by_field_name(name)?,
+        ))
+    }
+
+    /// Internal function to get the zippers for all named children of the contained node.
+    /// Unlike `child`, this never jumps to the subtree of an eval call.
+    pub(crate) fn named_children(&self) -> Vec<PolyglotZipper<'a>> {
+        let node = self.node();
+        let mut cursor = node.walk();
+        let children: Vec<Node<'a>> = node.named_children(&mut cursor).collect();
+        children
+            .into_iter()
+            .map(|c| Self::from_impl(self.tree, c))
+            .collect()
+    }
+
+    /// Internal function to get the value of the contained node if it is a string literal, with escape sequences decoded.
+    pub(crate) fn string_literal_value(&self) -> Option<String> {
+        self.tree.string_literal_value(self.node())
+    }
+
+    /// Internal function to get the zipper for the parent node, within the same tree.
+    pub(crate) fn parent(&self) -> Option<PolyglotZipper<'a>> {
+        Some(Self::from_impl(self.tree, self.node().parent()?))
+    }
+
     /// Returns true if the contained node is a polyglot eval call.
     pub fn is_polyglot_eval_call(&self) -> bool {
         self.tree.is_polyglot_eval_call(self.node())
     }
 
+    /// Returns true if the contained node is a polyglot eval call evaluating a file that is already being evaluated by an enclosing call,
+    /// such as `a.py` evaluating `b.js` which evaluates `a.py` again.
+    /// Such calls have no subtree, so that the tree is finite, and their kind is "cyclic_eval_reference".
+    pub fn is_cyclic_eval_reference(&self) -> bool {
+        self.tree.cyclic_references.contains_key(&self.node().id())
+    }
+
+    /// Get the path of the file this cyclic eval reference evaluates, which is the file of an enclosing tree.
+    /// The path is the key the `SourceResolver` identifies the file with, which is its canonical path for files on disk.
+    ///
+    /// Returns an InvalidArgumentError if the contained node is not a cyclic eval reference.
+    pub fn get_cyclic_reference(&self) -> Result<&'a Path, InvalidArgumentError> {
+        match self.tree.cyclic_references.get(&self.node().id()) {
+            Some(file) => Ok(file.as_path()),
+            None => Err(InvalidArgumentError),
+        }
+    }
+
     /// Returns true if the contained node is a polyglot import call.
     pub fn is_polyglot_import_call(&self) -> bool {
         self.tree.is_polyglot_import_call(self.node())
@@ -43,21 +245,60 @@ impl PolyglotZipper<'_> {
         self.tree.is_polyglot_export_call(self.node())
     }
 
+    /// Returns true if the contained node looks up a host class, such as `Java.type("com.acme.Foo")`.
+    pub fn is_host_type_lookup(&self) -> bool {
+        self.tree.is_host_type_lookup(self.node())
+    }
+
+    /// Get the fully qualified name of the host class this lookup refers to.
+    ///
+    /// Returns an InvalidArgumentError if the contained node is not a host type lookup.
+    pub fn get_host_type_name(&self) -> Result<String, InvalidArgumentError> {
+        self.tree
+            .host_type_name(self.node())
+            .ok_or(InvalidArgumentError)
+    }
+
+    /// Get the zipper for the declaration of the host class this lookup refers to,
+    /// or None if the contained node is not a host type lookup or the class is not declared in a Java file of the project.
+    ///
+    /// Java files are searched recursively from the project directory of the build, which is the working directory of the outermost tree,
+    /// such as the directory of the file passed to `from_path`. Subtrees share it, even when they were read from other directories.
+    /// Trees built with `from` have no project directory, and never find a declaration.
+    /// Unlike eval calls, host type lookups are not entered when moving to their children, so that traversals stay finite.
+    pub fn host_type_declaration(&self) -> Option<PolyglotZipper<'a>> {
+        let name = self.get_host_type_name().ok()?;
+        let tree = self.tree.host_class_tree(name.as_str())?;
+        let simple_name = name.rsplit(['.', '$']).next()?;
+        let declaration = tree.find_type_declaration(tree.root_node(), simple_name)?;
+        Some(Self::from_impl(tree, declaration))
+    }
+
+    /// Returns true if the contained node defines a function, method or lambda, whose body only runs when called.
+    pub fn is_function_definition(&self) -> bool {
+        self.tree.is_function_definition(self.node())
+    }
+
     /// Get the contained node's type as a string.
     ///
-    /// For polyglot nodes, this is one of either `"polyglot_eval_call"`, `"polyglot_import_call"` or `"polyglot_export_call"`.
+    /// For polyglot nodes, this is one of either `"polyglot_eval_call"`, `"cyclic_eval_reference"`, `"polyglot_import_call"`,
+    /// `"polyglot_export_call"` or `"host_type_lookup"`.
     pub fn kind(&self) -> &str {
-        if self.is_polyglot_eval_call() {
+        if self.is_cyclic_eval_reference() {
+            return "cyclic_eval_reference";
+        } else if self.is_polyglot_eval_call() {
             return "polyglot_eval_call";
         } else if self.is_polyglot_import_call() {
             return "polyglot_import_call";
         } else if self.is_polyglot_export_call() {
             return "polyglot_export_call";
+        } else if self.is_host_type_lookup() {
+            return "host_type_lookup";
         }
         self.node().kind()
     }
     /// Get the contained node's source code as a string.
-    pub fn code(&self) -> &str {
+    pub fn code(&self) -> &'a str {
         self.tree.node_to_code(self.node())
     }
 
@@ -66,34 +307,261 @@ impl PolyglotZipper<'_> {
         self.node().start_position()
     }
 
+    /// Get the contained node's range in terms of bytes.
+    pub fn byte_range(&self) -> std::ops::Range<usize> {
+        self.node().byte_range()
+    }
+
     /// Get the contained node's end position in terms of rows and columns.
     pub fn end_position(&self) -> tree_sitter::Point {
         self.node().end_position()
     }
 
+    /// Get the contained node's range in terms of bytes in the file it physically appears in.
+    ///
+    /// Positions of nodes evaluated from a string literal are relative to the decoded code of the literal;
+    /// this maps them back through every enclosing literal, accounting for quotes and escape sequences,
+    /// onto the outermost file, which is the one returned by `get_file`.
+    /// For any other node, this is the same as `byte_range`.
+    pub fn host_byte_range(&self) -> std::ops::Range<usize> {
+        let range = self.byte_range();
+        self.tree.host_byte(range.start)..self.tree.host_byte(range.end)
+    }
+
+    /// Get the contained node's start position in terms of rows and columns in the file it physically appears in.
+    ///
+    /// This maps positions the same way as `host_byte_range`, so columns count bytes of the file in its own encoding.
+    pub fn host_position(&self) -> tree_sitter::Point {
+        self.tree
+            .host_point(self.byte_range().start, self.start_position())
+    }
+
+    /// Get the contained node's end position in terms of rows and columns in the file it physically appears in.
+    ///
+    /// This maps positions the same way as `host_byte_range`.
+    pub fn host_end_position(&self) -> tree_sitter::Point {
+        self.tree
+            .host_point(self.byte_range().end, self.end_position())
+    }
+
+    /// Get the contained node's start position, with its column counted in the units of the given encoding.
+    ///
+    /// The `*_position` methods count columns in bytes, as tree-sitter does, which is the same as `PositionEncoding::Utf8`.
+    pub fn start_position_in(&self, encoding: PositionEncoding) -> Point {
+        let point = self.start_position();
+        Self::encode_point(
+            self.line_prefix(self.byte_range().start, point),
+            point,
+            encoding,
+        )
+    }
+
+    /// Get the contained node's end position, with its column counted in the units of the given encoding.
+    pub fn end_position_in(&self, encoding: PositionEncoding) -> Point {
+        let point = self.end_position();
+        Self::encode_point(
+            self.line_prefix(self.byte_range().end, point),
+            point,
+            encoding,
+        )
+    }
+
+    /// Get the contained node's start position in the file it physically appears in, as given by `host_position`,
+    /// with its column counted in the units of the given encoding.
+    ///
+    /// Columns are measured on the decoded code of the file, so unlike those of `host_position`,
+    /// `PositionEncoding::Utf8` columns count UTF-8 bytes even if the file is in another encoding.
+    pub fn host_position_in(&self, encoding: PositionEncoding) -> Point {
+        let prefix = self
+            .tree
+            .host_line_prefix(self.byte_range().start, self.start_position());
+        Self::encode_point(prefix, self.host_position(), encoding)
+    }
+
+    /// Get the contained node's end position in the file it physically appears in, as given by `host_end_position`,
+    /// with its column counted in the units of the given encoding.
+    pub fn host_end_position_in(&self, encoding: PositionEncoding) -> Point {
+        let prefix = self
+            .tree
+            .host_line_prefix(self.byte_range().end, self.end_position());
+        Self::encode_point(prefix, self.host_end_position(), encoding)
+    }
+
+    /// Internal function to get the code of the line a position is on, up to that position.
+    fn line_prefix(&self, byte: usize, point: Point) -> Option<&'a str> {
+        self.tree.code.get(byte.checked_sub(point.column)?..byte)
+    }
+
+    /// Internal function to convert the column of a point to the given encoding, from the decoded code of its line up to it.
+    fn encode_point(line_prefix: Option<&str>, point: Point, encoding: PositionEncoding) -> Point {
+        match line_prefix {
+            Some(line) => Point::new(point.row, encoding.measure(line)),
+            None => point,
+        }
+    }
+
+    /// Get the name of the binding this import or export call refers to, with escape sequences decoded.
+    ///
+    /// Returns an InvalidArgumentError if the contained node is not an import or export call,
+    /// or if the binding name is not a string literal.
     pub fn get_binding_name(&self) -> Result<String, InvalidArgumentError> {
+        Ok(self.get_binding()?.name)
+    }
+
+    /// Get the binding this import or export call refers to.
+    ///
+    /// Both positional and keyword arguments are supported for Python calls, as well as the `@polyglot.export_value` decorator,
+    /// in which case the binding is named after the decorated definition.
+    /// Use a TypeInferrer to get the binding along with the type of the shared value.
+    ///
+    /// Returns an InvalidArgumentError if the contained node is not an import or export call,
+    /// or if the binding name is not a string literal.
+    pub fn get_binding(&self) -> Result<PolyglotBinding<'a>, InvalidArgumentError> {
         if self.is_polyglot_import_call() || self.is_polyglot_export_call() {
-            return match self.get_lang() {
-                Language::Python => match self.get_python_binding() {
-                    Some(s) => Ok(s),
-                    None => Err(InvalidArgumentError), // todo: make this into a proper error enum
-                },
-                Language::JavaScript => todo!(),
-                Language::Java => todo!(),
+            let binding = match self.get_lang() {
+                Language::Python => self.get_python_binding(),
+                Language::JavaScript => self.get_js_binding(),
+                Language::Java => self.get_java_binding(),
             };
+            return binding.ok_or(InvalidArgumentError); // todo: make this into a proper error enum
         }
         Err(InvalidArgumentError)
     }
 
-    fn get_python_binding(&self) -> Option<String> {
-        Some(String::from(self.child(1)?.child(1)?.code()))
+    /// Internal function to get the arguments of an eval call, or None if the contained node is not one.
+    pub(crate) fn get_eval_arguments(&self) -> Option<EvalArguments<'a>> {
+        if !self.is_polyglot_eval_call() {
+            return None;
+        }
+        let arguments: Vec<PolyglotZipper<'a>> = self
+            .child_by_field_name("arguments")?
+            .named_children()
+            .into_iter()
+            .filter(|a| !a.kind().eq("comment"))
+            .collect();
+        let mut result = EvalArguments {
+            language: None,
+            source: None,
+            is_file: false,
+        };
+        match self.get_lang() {
+            Language::Python => {
+                // polyglot.eval(language, string) or polyglot.eval(language, path=path)
+                for (i, arg) in arguments.into_iter().enumerate() {
+                    if arg.kind().eq("keyword_argument") {
+                        let value = arg.child_by_field_name("value");
+                        match arg.child_by_field_name("name").map(|n| n.code()) {
+                            Some("language") => result.language = value,
+                            Some("string") => result.source = value,
+                            Some("path") => {
+                                result.source = value;
+                                result.is_file = true;
+                            }
+                            _ => (),
+                        }
+                    } else if i == 0 {
+                        result.language = Some(arg);
+                    } else if i == 1 {
+                        result.source = Some(arg);
+                    }
+                }
+            }
+            Language::JavaScript => {
+                // Polyglot.eval(language, code) and Polyglot.evalFile(language, path)
+                let mut arguments = arguments.into_iter();
+                result.language = arguments.next();
+                result.source = arguments.next();
+                result.is_file = self
+                    .child_by_field_name("function")
+                    .is_some_and(|f| f.code().ends_with("evalFile"));
+            }
+            Language::Java => {
+                // context.eval(language, code) and context.eval(source)
+                if arguments.len() == 1 {
+                    result.source = arguments.into_iter().next();
+                } else {
+                    let mut arguments = arguments.into_iter();
+                    result.language = arguments.next();
+                    result.source = arguments.next();
+                }
+            }
+        }
+        Some(result)
+    }
+
+    fn get_python_binding(&self) -> Option<PolyglotBinding<'a>> {
+        let node = self.node();
+        if node.kind().eq("decorator") {
+            let definition = node.parent()?.child_by_field_name("definition")?;
+            let name = definition.child_by_field_name("name")?;
+            return Some(PolyglotBinding {
+                name: String::from(self.tree.node_to_code(name)),
+                value: Some(Self::from_impl(self.tree, definition)),
+            });
+        }
+
+        // polyglot.export_value(name, value) and polyglot.import_value(name)
+        let mut name = None;
+        let mut value = None;
+        let arguments = node.child_by_field_name("arguments")?;
+        let mut cursor = arguments.walk();
+        for (i, arg) in arguments.named_children(&mut cursor).enumerate() {
+            if arg.kind().eq("keyword_argument") {
+                let arg_value = arg.child_by_field_name("value")?;
+                match self.tree.node_to_code(arg.child_by_field_name("name")?) {
+                    "name" => name = Some(arg_value),
+                    "value" => value = Some(arg_value),
+                    _ => (),
+                }
+            } else if i == 0 {
+                name = Some(arg);
+            } else if i == 1 {
+                value = Some(arg);
+            }
+        }
+        self.make_binding(name?, value)
+    }
+
+    fn get_js_binding(&self) -> Option<PolyglotBinding<'a>> {
+        // Polyglot.export(name, value) and Polyglot.import(name)
+        let arguments = self.node().child_by_field_name("arguments")?;
+        self.make_binding(arguments.named_child(0)?, arguments.named_child(1))
+    }
+
+    fn get_java_binding(&self) -> Option<PolyglotBinding<'a>> {
+        // bindings.putMember(name, value) and bindings.getMember(name)
+        let arguments = self.node().child_by_field_name("arguments")?;
+        self.make_binding(arguments.named_child(0)?, arguments.named_child(1))
+    }
+
+    fn make_binding(&self, name: Node<'a>, value: Option<Node<'a>>) -> Option<PolyglotBinding<'a>> {
+        Some(PolyglotBinding {
+            name: self.tree.string_literal_value(name)?,
+            value: match self.is_polyglot_export_call() {
+                true => Some(Self::from_impl(self.tree, value?)),
+                false => None,
+            },
+        })
     }
 
     /// Get the Language associated with the contained node.
-    pub fn get_lang(&self) -> &Language {
+    pub fn get_lang(&self) -> &'a Language {
         &self.tree.language
     }
 
+    /// Get the path of the file the contained node physically appears in.
+    /// Returns None if the tree was built from a code snippet rather than a file.
+    pub fn get_file(&self) -> Option<&'a Path> {
+        self.tree.file.as_deref()
+    }
+
+    /// Returns true if both zippers are in the same tree.
+    /// A file evaluated from several places in the same language is only built once per build, and all of them share a single tree;
+    /// files with the same contents at different paths have their own trees.
+    pub fn same_tree_as(&self, other: &PolyglotZipper) -> bool {
+        std::ptr::eq(self.tree, other.tree)
+    }
+
     /// Move this zipper to the first child of the contained node.
     /// Returns `true` if there were any children, otherwise returns `false` and does not move.
     pub fn goto_first_child(&mut self) -> bool {
@@ -118,7 +586,7 @@ impl PolyglotZipper<'_> {
     }
 
     /// Get the zipper for the child at the given index, where zero represents the first child.
-    pub fn child(&self, i: usize) -> Option<PolyglotZipper> {
+    pub fn child(&self, i: usize) -> Option<PolyglotZipper<'a>> {
         if self.is_polyglot_eval_call() {
             // if we are an eval call, we actually want to jump to the corresponding subtree
             let my_id = self.node().id();
@@ -130,12 +598,12 @@ impl PolyglotZipper<'_> {
     }
 
     /// Get the zipper for the next sibling node.
-    pub fn next_sibling(&self) -> Option<PolyglotZipper> {
+    pub fn next_sibling(&self) -> Option<PolyglotZipper<'a>> {
         Some(Self::from_impl(self.tree, self.node().next_sibling()?))
     }
 
     /// Get the zipper for the previous sibling node.
-    pub fn prev_sibling(&self) -> Option<PolyglotZipper> {
+    pub fn prev_sibling(&self) -> Option<PolyglotZipper<'a>> {
         Some(Self::from_impl(self.tree, self.node().prev_sibling()?))
     }
 }
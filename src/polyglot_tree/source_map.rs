@@ -0,0 +1,236 @@
+use super::super::util::{Language, SourceEncoding};
+use std::ops::Range;
+use std::sync::Arc;
+use tree_sitter::Point;
+
+/// A range of a file written in a single language, as returned by `PolyglotTree::language_regions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageRegion {
+    byte_range: Range<usize>,
+    language: Language,
+    depth: usize,
+}
+
+impl LanguageRegion {
+    pub(crate) fn new(
+        byte_range: Range<usize>,
+        language: Language,
+        depth: usize,
+    ) -> LanguageRegion {
+        LanguageRegion {
+            byte_range,
+            language,
+            depth,
+        }
+    }
+
+    /// Returns the byte range of the region in the file.
+    pub fn byte_range(&self) -> Range<usize> {
+        self.byte_range.clone()
+    }
+
+    /// Returns the language the region is written in.
+    pub fn get_lang(&self) -> Language {
+        self.language
+    }
+
+    /// Returns the number of string literals the region is nested in, 0 being the code of the file itself.
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// The decoded code of a file along with its line table, built once per tree read from the file
+/// and shared by the origins of every string literal evaluated in it, at any depth.
+#[derive(Debug)]
+pub(crate) struct HostSource {
+    code: Arc<str>,
+    /// The byte offsets at which the lines of the code start.
+    lines: Vec<usize>,
+    /// The mapping of the code to the file if it was not UTF-8.
+    transcoding: Option<Arc<Transcoding>>,
+}
+
+impl HostSource {
+    pub fn new(code: &str, transcoding: Option<Arc<Transcoding>>) -> HostSource {
+        HostSource {
+            code: Arc::from(code),
+            lines: std::iter::once(0)
+                .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            transcoding,
+        }
+    }
+
+    /// Returns the row and column a byte offset of the code is at.
+    fn point(&self, byte: usize) -> Point {
+        let row = self.lines.partition_point(|l| *l <= byte) - 1;
+        Point::new(row, byte - self.lines[row])
+    }
+}
+
+/// Where the code of a tree built from a string literal physically appears, so that its positions can be mapped back onto the host file.
+///
+/// Trees embedded in other embedded trees chain to the origin of their parent, down to the outermost tree read from the file.
+#[derive(Debug)]
+pub(crate) struct EmbeddedOrigin {
+    /// Pairs of (offset in the embedded code, offset in the enclosing code), as returned by `util::unescape_string_literal_mapped`
+    /// but shifted to the position of the literal in the enclosing code.
+    segments: Vec<(usize, usize)>,
+    parent: Option<Arc<EmbeddedOrigin>>,
+    /// The host file, shared by every origin of the chain.
+    host: Arc<HostSource>,
+}
+
+impl EmbeddedOrigin {
+    /// Creates the origin of code decoded from a literal starting at `literal_start` in the enclosing code.
+    ///
+    /// `parent` is the origin of the enclosing code if it is itself embedded, and `host` the file the outermost code was read from.
+    pub fn new(
+        segments: Vec<(usize, usize)>,
+        literal_start: usize,
+        parent: Option<Arc<EmbeddedOrigin>>,
+        host: Arc<HostSource>,
+    ) -> EmbeddedOrigin {
+        EmbeddedOrigin {
+            segments: segments
+                .into_iter()
+                .map(|(embedded, enclosing)| (embedded, literal_start + enclosing))
+                .collect(),
+            parent,
+            host,
+        }
+    }
+
+    /// Returns the host file.
+    pub fn host(&self) -> &Arc<HostSource> {
+        &self.host
+    }
+
+    /// Returns the code of the host file.
+    pub fn host_code(&self) -> &str {
+        &self.host.code
+    }
+
+    /// Returns the mapping of the host code to the host file, if the file was not UTF-8.
+    pub fn host_transcoding(&self) -> Option<&Arc<Transcoding>> {
+        self.host.transcoding.as_ref()
+    }
+
+    /// Maps a byte offset in the embedded code to the corresponding offset in the host code.
+    pub fn host_byte(&self, byte: usize) -> usize {
+        let index = self.segments.partition_point(|(e, _)| *e <= byte);
+        let enclosing = match index.checked_sub(1).map(|i| self.segments[i]) {
+            Some((embedded, enclosing)) => enclosing + byte - embedded,
+            None => byte,
+        };
+        match &self.parent {
+            Some(parent) => parent.host_byte(enclosing),
+            None => enclosing,
+        }
+    }
+
+    /// Maps a byte offset in the embedded code to the row and column it appears at in the host code.
+    pub fn host_point(&self, byte: usize) -> Point {
+        self.host.point(self.host_byte(byte))
+    }
+}
+
+/// A run of characters that take the same number of bytes in the decoded code and in the original file.
+#[derive(Debug)]
+struct Run {
+    decoded: usize,
+    original: usize,
+    decoded_width: usize,
+    original_width: usize,
+}
+
+/// The mapping between the byte offsets of code decoded from a file that is not UTF-8, and those of the file itself.
+#[derive(Debug)]
+pub(crate) struct Transcoding {
+    runs: Vec<Run>,
+}
+
+impl Transcoding {
+    /// Decodes the contents of a file from the given encoding, skipping its byte order mark if there is one.
+    ///
+    /// Returns the decoded code, along with its mapping to the file unless the code and the file are identical.
+    /// Invalid sequences are replaced with `char::REPLACEMENT_CHARACTER`.
+    pub fn decode(bytes: &[u8], encoding: SourceEncoding) -> (String, Option<Transcoding>) {
+        let bom: &[u8] = match encoding {
+            SourceEncoding::Utf8 => &[0xef, 0xbb, 0xbf],
+            SourceEncoding::Utf16Le => &[0xff, 0xfe],
+            SourceEncoding::Utf16Be => &[0xfe, 0xff],
+            SourceEncoding::Latin1 => &[],
+        };
+        let start = match bytes.starts_with(bom) {
+            true => bom.len(),
+            false => 0,
+        };
+        let body = &bytes[start..];
+
+        let chars: Vec<(char, usize)> = match encoding {
+            SourceEncoding::Utf8 => match std::str::from_utf8(body) {
+                Ok(code) if start == 0 => return (String::from(code), None),
+                _ => String::from_utf8_lossy(body)
+                    .char_indices()
+                    .map(|(_, c)| (c, c.len_utf8()))
+                    .collect(),
+            },
+            SourceEncoding::Utf16Le | SourceEncoding::Utf16Be => {
+                let units = body.chunks_exact(2).map(|b| match encoding {
+                    SourceEncoding::Utf16Le => u16::from_le_bytes([b[0], b[1]]),
+                    _ => u16::from_be_bytes([b[0], b[1]]),
+                });
+                char::decode_utf16(units)
+                    .map(|c| match c {
+                        Ok(c) => (c, 2 * c.len_utf16()),
+                        Err(_) => (char::REPLACEMENT_CHARACTER, 2),
+                    })
+                    .collect()
+            }
+            SourceEncoding::Latin1 => body.iter().map(|b| (char::from(*b), 1)).collect(),
+        };
+
+        let mut code = String::with_capacity(body.len());
+        let mut runs: Vec<Run> = Vec::new();
+        let mut original = start;
+        for (c, original_width) in chars {
+            let decoded_width = c.len_utf8();
+            let same_run = runs.last().is_some_and(|r| {
+                r.decoded_width == decoded_width && r.original_width == original_width
+            });
+            if !same_run {
+                runs.push(Run {
+                    decoded: code.len(),
+                    original,
+                    decoded_width,
+                    original_width,
+                });
+            }
+            code.push(c);
+            original += original_width;
+        }
+        if runs.is_empty() {
+            runs.push(Run {
+                decoded: 0,
+                original: start,
+                decoded_width: 1,
+                original_width: 1,
+            });
+        }
+        (code, Some(Transcoding { runs }))
+    }
+
+    /// Maps a byte offset in the decoded code to the corresponding offset in the file.
+    pub fn to_original(&self, byte: usize) -> usize {
+        let run = &self.runs[self.runs.partition_point(|r| r.decoded <= byte).max(1) - 1];
+        run.original + (byte - run.decoded) / run.decoded_width * run.original_width
+    }
+
+    /// Maps a byte offset in the file to the corresponding offset in the decoded code.
+    pub fn to_decoded(&self, byte: usize) -> usize {
+        let run = &self.runs[self.runs.partition_point(|r| r.original <= byte).max(1) - 1];
+        run.decoded + byte.saturating_sub(run.original) / run.original_width * run.decoded_width
+    }
+}
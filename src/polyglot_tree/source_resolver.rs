@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// A trait to locate and read the files evaluated by polyglot calls, such as with `evalFile` or the `path` argument of Python's `polyglot.eval`.
+///
+/// Trees built from a file read it and every file it evaluates, at any depth, through the same resolver,
+/// which allows building trees from sources that are not on disk, such as git blobs, archives or test fixtures.
+pub trait SourceResolver: Send + Sync {
+    /// Returns the path of the file that `path` refers to in code whose working directory is `working_dir`, along with its contents.
+    ///
+    /// The returned path names the file in the tree, for instance in `PolyglotZipper::get_file`,
+    /// and its parent is the working directory of the code it contains.
+    /// Returns None if there is no such file, or if it cannot be read.
+    ///
+    /// A file at `path` relative to `working_dir`, once read, is assumed to be the one `path` refers to,
+    /// so that files already read are not resolved again.
+    fn resolve(&self, working_dir: &Path, path: &Path) -> Option<(PathBuf, Vec<u8>)>;
+
+    /// Returns the key identifying the file at the given path, such as a path returned by `resolve`:
+    /// two paths name the same file if and only if their keys are equal.
+    /// Keys are used to build each file once, and to detect files evaluating themselves.
+    ///
+    /// The default implementation normalizes the path lexically, by removing `.` components and resolving `..` components.
+    fn file_key(&self, file: &Path) -> PathBuf {
+        normalize(file)
+    }
+
+    /// Returns the paths of the files with the given extension in `dir` and its subdirectories, sorted,
+    /// such as the Java files host classes are looked up in. Each path can be given to `resolve` with an empty working directory.
+    ///
+    /// The default implementation lists no files, for resolvers that cannot enumerate their sources.
+    fn list_files(&self, dir: &Path, extension: &str) -> Vec<PathBuf> {
+        let _ = (dir, extension);
+        Vec::new()
+    }
+}
+
+/// A resolver reading files from the real filesystem, relative to the working directory.
+/// This is the resolver used when none is given.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileSystemResolver;
+
+impl SourceResolver for FileSystemResolver {
+    fn resolve(&self, working_dir: &Path, path: &Path) -> Option<(PathBuf, Vec<u8>)> {
+        let file = working_dir.join(path);
+        let contents = std::fs::read(&file).ok()?;
+        Some((file, contents))
+    }
+
+    /// Files on disk are identified by their canonical path, which resolves symbolic links,
+    /// or by their normalized path if they do not exist.
+    fn file_key(&self, file: &Path) -> PathBuf {
+        file.canonicalize().unwrap_or_else(|_| normalize(file))
+    }
+
+    /// Walks `dir` recursively, skipping hidden directories.
+    fn list_files(&self, dir: &Path, extension: &str) -> Vec<PathBuf> {
+        fn collect(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) {
+            let entries = match std::fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => return,
+            };
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+                if path.is_dir() {
+                    if !hidden {
+                        collect(path.as_path(), extension, files);
+                    }
+                } else if path.extension().is_some_and(|e| e == extension) {
+                    files.push(path);
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        collect(dir, extension, &mut files);
+        files.sort();
+        files
+    }
+}
+
+/// A resolver reading files from a map of paths to contents, which never touches the disk.
+///
+/// Paths are joined to the working directory and normalized, by removing `.` and resolving `..` components lexically,
+/// before being looked up.
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use polyglot_ast::{InMemoryResolver, SourceResolver};
+///
+/// let mut resolver = InMemoryResolver::new();
+/// resolver.insert("src/lib.py", "x = 42");
+///
+/// let (file, contents) = resolver.resolve(Path::new("src/app"), Path::new("../lib.py")).expect("The file was inserted");
+/// assert_eq!(file, Path::new("src/lib.py"));
+/// assert_eq!(contents, b"x = 42");
+/// assert!(resolver.resolve(Path::new("src"), Path::new("other.py")).is_none());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryResolver {
+    files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl InMemoryResolver {
+    /// Initializes a new InMemoryResolver instance, with no files.
+    pub fn new() -> Self {
+        InMemoryResolver {
+            files: HashMap::new(),
+        }
+    }
+
+    /// Adds a file with the given contents, replacing any previous file at the same path.
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+}
+
+impl SourceResolver for InMemoryResolver {
+    fn resolve(&self, working_dir: &Path, path: &Path) -> Option<(PathBuf, Vec<u8>)> {
+        let file = normalize(&working_dir.join(path));
+        let contents = self.files.get(&file)?.clone();
+        Some((file, contents))
+    }
+
+    fn list_files(&self, dir: &Path, extension: &str) -> Vec<PathBuf> {
+        let dir = normalize(dir);
+        let mut files: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|f| f.starts_with(&dir) && f.extension().is_some_and(|e| e == extension))
+            .cloned()
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// A resolver looking files up in the working directory, then in each directory of a search path in order,
+/// like `PYTHONPATH` or `NODE_PATH` do.
+/// Files are read through another resolver, which is the real filesystem unless given.
+pub struct SearchPathResolver<R: SourceResolver = FileSystemResolver> {
+    search_path: Vec<PathBuf>,
+    resolver: R,
+}
+
+impl SearchPathResolver<FileSystemResolver> {
+    /// Initializes a new SearchPathResolver instance reading files from the real filesystem.
+    pub fn new(search_path: Vec<PathBuf>) -> Self {
+        Self::with_resolver(search_path, FileSystemResolver)
+    }
+}
+
+impl<R: SourceResolver> SearchPathResolver<R> {
+    /// Initializes a new SearchPathResolver instance reading files through the given resolver.
+    pub fn with_resolver(search_path: Vec<PathBuf>, resolver: R) -> Self {
+        SearchPathResolver {
+            search_path,
+            resolver,
+        }
+    }
+
+    /// Returns the directories searched after the working directory, in order.
+    pub fn get_search_path(&self) -> &[PathBuf] {
+        self.search_path.as_slice()
+    }
+}
+
+impl<R: SourceResolver> SourceResolver for SearchPathResolver<R> {
+    fn resolve(&self, working_dir: &Path, path: &Path) -> Option<(PathBuf, Vec<u8>)> {
+        std::iter::once(working_dir)
+            .chain(self.search_path.iter().map(PathBuf::as_path))
+            .find_map(|dir| self.resolver.resolve(dir, path))
+    }
+
+    /// Lists the files of `dir` only, as the search path holds libraries rather than the project's own files.
+    fn list_files(&self, dir: &Path, extension: &str) -> Vec<PathBuf> {
+        self.resolver.list_files(dir, extension)
+    }
+
+    fn file_key(&self, file: &Path) -> PathBuf {
+        self.resolver.file_key(file)
+    }
+}
+
+/// Removes the `.` components of a path and resolves its `..` components without accessing the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(result.components().next_back(), Some(Component::Normal(_))) {
+                    result.pop();
+                } else {
+                    result.push(component);
+                }
+            }
+            other => result.push(other),
+        }
+    }
+    result
+}
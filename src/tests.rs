@@ -1,4 +1,6 @@
 use super::*;
+use polyglot_tree::polyglot_processor::PolyglotCallKind;
+use std::ffi::OsStr;
 use std::path::PathBuf;
 
 fn assert_ast_eq(test_file: &str, expected_file: &str, lang: util::Language) {
@@ -48,3 +50,1410 @@ fn java_test() {
 
     assert_ast_eq(file_test, file_expect, util::Language::Java)
 }
+
+fn collect_bindings(zip: Option<PolyglotZipper>, bindings: &mut Vec<(String, Option<String>)>) {
+    if let Some(zip) = zip {
+        if zip.is_polyglot_import_call() || zip.is_polyglot_export_call() {
+            let binding = zip.get_binding().expect("Binding names should be literals");
+            let value = binding.value().map(|v| String::from(v.code()));
+            bindings.push((String::from(binding.name()), value));
+        }
+        collect_bindings(zip.child(0), bindings);
+        collect_bindings(zip.next_sibling(), bindings);
+    }
+}
+
+fn assert_bindings_eq(test_file: &str, lang: util::Language, expected: &[(&str, Option<&str>)]) {
+    let tree = PolyglotTree::from_path(PathBuf::from(test_file), lang)
+        .unwrap_or_else(|| panic!("AST creation failed for test file {test_file}"));
+    let mut actual = Vec::new();
+    collect_bindings(Some(PolyglotZipper::from(&tree)), &mut actual);
+
+    let expected: Vec<(String, Option<String>)> = expected
+        .iter()
+        .map(|(n, v)| (String::from(*n), v.map(String::from)))
+        .collect();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn python_bindings_test() {
+    assert_bindings_eq(
+        "TestSamples/bindings.py",
+        util::Language::Python,
+        &[
+            ("greeting", Some("\"hello\\tworld\"")),
+            ("add", Some("def add(a, b):\n    return a + b")),
+            ("z", None),
+        ],
+    );
+    assert_bindings_eq(
+        "TestSamples/export_x.py",
+        util::Language::Python,
+        &[("x", Some("x")), ("x", None)],
+    );
+}
+
+#[test]
+fn js_bindings_test() {
+    assert_bindings_eq(
+        "TestSamples/bindings.js",
+        util::Language::JavaScript,
+        &[("xA", Some("42")), ("y", None)],
+    );
+}
+
+#[test]
+fn java_bindings_test() {
+    assert_bindings_eq(
+        "TestSamples/JavaTest.java",
+        util::Language::Java,
+        &[("test", None), ("null", None)],
+    );
+}
+
+#[test]
+fn du_builder_test() {
+    let file = PathBuf::from("TestSamples/export_x.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+    let mut du = DUBuilder::new();
+    tree.apply(&mut du);
+
+    assert!(du.get_inconsistencies().is_empty());
+    let exports = du.get_exports().get("x").expect("x is exported");
+    assert_eq!(exports.len(), 1);
+    assert_eq!(
+        exports[0].get_file(),
+        Some(PathBuf::from("TestSamples/export_x.py").as_path())
+    );
+    assert_eq!(exports[0].get_position(), tree_sitter::Point::new(3, 0));
+    let imports = du.get_imports().get("x").expect("x is imported");
+    assert_eq!(
+        imports[0].get_file(),
+        Some(PathBuf::from("TestSamples/import_x.py").as_path())
+    );
+
+    let file = PathBuf::from("TestSamples/bindings.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+    tree.apply(&mut du);
+
+    let inconsistencies = du.get_inconsistencies();
+    assert_eq!(inconsistencies.get_imported_not_exported(), ["z"]);
+    assert_eq!(
+        inconsistencies.get_exported_not_imported(),
+        ["add", "greeting"]
+    );
+}
+
+#[test]
+fn execution_order_test() {
+    let file = PathBuf::from("TestSamples/import_before_export.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut checker = ExecutionOrderChecker::new();
+    tree.apply(&mut checker);
+
+    let kinds: Vec<PolyglotCallKind> = checker.get_events().iter().map(|e| e.get_kind()).collect();
+    assert_eq!(
+        kinds,
+        [
+            PolyglotCallKind::Import,
+            PolyglotCallKind::Eval,
+            PolyglotCallKind::Export,
+            PolyglotCallKind::Import,
+            PolyglotCallKind::Import,
+            PolyglotCallKind::Export,
+        ]
+    );
+
+    let early: Vec<(Option<&str>, usize, bool)> = checker
+        .get_early_imports()
+        .iter()
+        .map(|e| {
+            (
+                e.get_name(),
+                e.get_occurrence().get_position().row,
+                e.is_deferred(),
+            )
+        })
+        .collect();
+    assert_eq!(early, [(Some("x"), 0, false), (Some("y"), 4, true)]);
+
+    let file = PathBuf::from("TestSamples/export_x.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+    tree.apply(&mut checker);
+    assert!(checker.get_early_imports().is_empty());
+}
+
+fn call_graph_edges(builder: &CallGraphBuilder) -> Vec<(Option<&str>, Option<&str>, bool)> {
+    let name = |i: usize| builder.get_functions()[i].get_name();
+    builder
+        .get_edges()
+        .iter()
+        .map(|e| {
+            (
+                e.get_caller().and_then(name),
+                name(e.get_callee()),
+                e.is_polyglot(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn call_graph_test() {
+    let file = PathBuf::from("TestSamples/call_graph.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+    let mut builder = CallGraphBuilder::new();
+    tree.apply(&mut builder);
+
+    let names: Vec<Option<&str>> = builder
+        .get_functions()
+        .iter()
+        .map(|f| f.get_name())
+        .collect();
+    assert_eq!(names, [Some("square"), Some("helper")]);
+    assert_eq!(
+        call_graph_edges(&builder),
+        [
+            (Some("helper"), Some("square"), false),
+            (None, Some("square"), true)
+        ]
+    );
+    let js_call = builder.get_edges()[1].get_call_site();
+    assert!(matches!(js_call.get_lang(), util::Language::JavaScript));
+
+    let file = PathBuf::from("TestSamples/CallGraphTest.java");
+    let tree = PolyglotTree::from_path(file, util::Language::Java).expect("This test file exists");
+    tree.apply(&mut builder);
+
+    let names: Vec<Option<&str>> = builder
+        .get_functions()
+        .iter()
+        .map(|f| f.get_name())
+        .collect();
+    assert_eq!(names, [Some("main"), None, Some("helper")]);
+    assert_eq!(
+        call_graph_edges(&builder),
+        [
+            (Some("main"), None, true),
+            (Some("main"), Some("helper"), false)
+        ]
+    );
+}
+
+#[test]
+fn arity_test() {
+    use polyglot_tree::polyglot_processor::arity::{Arity, CallIssueKind};
+
+    let file = PathBuf::from("TestSamples/arity.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut checker = ArityChecker::new();
+    tree.apply(&mut checker);
+
+    let issues: Vec<_> = checker
+        .get_issues()
+        .iter()
+        .map(|i| {
+            let counts = match i.get_kind() {
+                CallIssueKind::ArgumentCount { expected, actual } => {
+                    Some((expected.get_min(), expected.get_max(), *actual))
+                }
+                CallIssueKind::NotCallable => None,
+            };
+            let row = i.get_call_site().start_position().row;
+            (row, i.get_definition().kind(), counts)
+        })
+        .collect();
+    assert_eq!(
+        issues,
+        [
+            (3, "function_definition", Some((1, Some(1), 2))),
+            (6, "lambda", Some((1, Some(2), 0))),
+            (7, "integer", None),
+        ]
+    );
+
+    // classes are called with the arguments of their constructor, and static methods have no implicit parameter
+    let tree = PolyglotTree::from(
+        "Polyglot.eval(\"python\", \"class P:\\n  def __init__(self, x): pass\\n  @staticmethod\\n  def s(a): pass\\n  @classmethod\\n  def c(cls, a): pass\\nP\")(1, 2);\nPolyglot.eval(\"js\", \"class A { constructor(a, b) {} }\\nA\")(1);",
+        util::Language::JavaScript,
+    )
+    .expect("This code parses");
+    let mut checker = ArityChecker::new();
+    tree.apply(&mut checker);
+    let issues: Vec<_> = checker
+        .get_issues()
+        .iter()
+        .map(|i| match i.get_kind() {
+            CallIssueKind::ArgumentCount { expected, actual } => Some((
+                i.get_definition().kind(),
+                expected.get_min(),
+                expected.get_max(),
+                *actual,
+            )),
+            CallIssueKind::NotCallable => None,
+        })
+        .collect();
+    assert_eq!(
+        issues,
+        [
+            Some(("class_definition", 1, Some(1), 2)),
+            Some(("class_declaration", 2, Some(2), 1)),
+        ]
+    );
+    let root = Some(PolyglotZipper::from(&tree));
+    let arity = |code| {
+        let def = find_node(root.clone(), code).expect("The method is in the test code");
+        Arity::from(&def).map(|a| a.get_min())
+    };
+    assert_eq!(arity("def s(a): pass"), Some(1));
+    assert_eq!(arity("def c(cls, a): pass"), Some(1));
+}
+
+#[test]
+fn taint_test() {
+    use util::Language::{JavaScript, Python};
+
+    let file = PathBuf::from("TestSamples/taint.js");
+    let tree = PolyglotTree::from_path(file, JavaScript).expect("This test file exists");
+    let mut analyzer = TaintAnalyzer::new(
+        vec![
+            TaintPattern::new(JavaScript, "req.body"),
+            TaintPattern::new(Python, "input"),
+        ],
+        vec![
+            TaintPattern::new(Python, "os.system"),
+            TaintPattern::new(JavaScript, "*.exec"),
+        ],
+    );
+    tree.apply(&mut analyzer);
+
+    let paths: Vec<Vec<&str>> = analyzer
+        .get_paths()
+        .iter()
+        .map(|p| p.get_steps().iter().map(|s| s.code()).collect())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            vec![
+                "req.body",
+                "req.body.command",
+                "cmd",
+                "polyglot.import_value(\"cmd\")",
+                "command",
+                "\"echo \" + command",
+                "os.system(\"echo \" + command)",
+            ],
+            vec![
+                "input()",
+                "Polyglot.eval(\"python\", \"input()\")",
+                "line",
+                "require('child_process').exec(line)",
+            ],
+        ]
+    );
+
+    let sink = analyzer.get_paths()[0].get_sink();
+    assert_eq!(
+        sink.get_file(),
+        Some(PathBuf::from("TestSamples/run_cmd.py").as_path())
+    );
+    assert_eq!(sink.start_position(), tree_sitter::Point::new(4, 0));
+}
+
+fn find_node<'a>(zip: Option<PolyglotZipper<'a>>, code: &str) -> Option<PolyglotZipper<'a>> {
+    let zip = zip?;
+    if zip.code().eq(code) {
+        return Some(zip);
+    }
+    find_node(zip.child(0), code).or_else(|| find_node(zip.next_sibling(), code))
+}
+
+#[test]
+fn slicing_test() {
+    let file = PathBuf::from("TestSamples/export_x.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+    let criterion = find_node(Some(PolyglotZipper::from(&tree)), "print(y)")
+        .expect("print(y) is in import_x.py")
+        .to_handle();
+    let mut slicer = BackwardSlicer::new(criterion);
+    tree.apply(&mut slicer);
+
+    let slice: Vec<(&str, &str)> = slicer
+        .get_slice()
+        .iter()
+        .map(|h| {
+            (
+                h.get_file().and_then(|f| f.to_str()).unwrap_or_default(),
+                h.code(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        slice,
+        [
+            ("TestSamples/export_x.py", "x = 42"),
+            (
+                "TestSamples/export_x.py",
+                "polyglot.export_value(name=\"x\", value=x)"
+            ),
+            (
+                "TestSamples/export_x.py",
+                "polyglot.eval(path=\"import_x.py\", language=\"python\")"
+            ),
+            (
+                "TestSamples/import_x.py",
+                "y = polyglot.import_value(name=\"x\")"
+            ),
+            ("TestSamples/import_x.py", "print(y)"),
+        ]
+    );
+
+    // statements of embedded snippets are found and sorted by their position in the host
+    let tree = PolyglotTree::from(
+        "const a = 1;\nPolyglot.eval(\"python\", \"x = 1\\nprint(x)\");\nPolyglot.eval(\"python\", \"y = 2\\nprint(y)\");",
+        util::Language::JavaScript,
+    )
+    .expect("This code parses");
+    let criterion = find_node(Some(PolyglotZipper::from(&tree)), "print(y)")
+        .expect("print(y) is in the second snippet")
+        .to_handle();
+    let mut slicer = BackwardSlicer::new(criterion);
+    tree.apply(&mut slicer);
+    let slice: Vec<&str> = slicer.get_slice().iter().map(|h| h.code()).collect();
+    assert_eq!(
+        slice,
+        [
+            "Polyglot.eval(\"python\", \"y = 2\\nprint(y)\");",
+            "y = 2",
+            "print(y)"
+        ]
+    );
+}
+
+#[test]
+fn type_inference_test() {
+    use polyglot_tree::polyglot_processor::type_inference::Signature;
+    use ValueType::{Function, Number, Object, Unknown};
+
+    let file = PathBuf::from("TestSamples/model.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut inferrer = TypeInferrer::new();
+    tree.apply(&mut inferrer);
+
+    let function = |min, max: Option<usize>| {
+        move |t: &ValueType| match t {
+            Function(s) => s.get_arity().get_min() == min && s.get_arity().get_max() == max,
+            _ => false,
+        }
+    };
+    let train = inferrer.get_type("train").expect("train is exported");
+    assert!(function(1, None)(train));
+    assert_eq!(inferrer.get_type("rate"), Some(&Number));
+    assert_eq!(inferrer.get_type("label"), Some(&ValueType::String));
+    assert_eq!(
+        inferrer.get_type("config"),
+        Some(&Object(vec![
+            (String::from("name"), ValueType::String),
+            (String::from("layers"), Unknown),
+        ]))
+    );
+
+    let members = match inferrer.get_type("Model") {
+        Some(ValueType::Class {
+            name,
+            constructor,
+            members,
+        }) => {
+            assert_eq!(name.as_deref(), Some("Model"));
+            assert_eq!(
+                constructor.as_ref().map(Signature::get_parameters),
+                Some([String::from("name")].as_slice())
+            );
+            members.clone()
+        }
+        other => panic!("Model should be a class, got {other:?}"),
+    };
+    let names: Vec<_> = members.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, ["version", "name", "predict"]);
+    assert!(function(1, Some(2))(&members[2].1));
+    assert_eq!(inferrer.get_type("model"), Some(&Object(members)));
+
+    let root = Some(PolyglotZipper::from(&tree));
+    let export = find_node(root.clone(), "polyglot.export_value(\"rate\", -0.1)")
+        .and_then(|statement| statement.child(0))
+        .expect("rate is exported in model.py");
+    assert_eq!(
+        inferrer.get_binding(&export).unwrap().value_type(),
+        Some(&Number)
+    );
+    let import = find_node(root, "Polyglot.import(\"model\")").expect("model is imported");
+    let binding = inferrer.get_binding(&import).unwrap();
+    assert_eq!(binding.name(), "model");
+    assert!(matches!(binding.value_type(), Some(Object(_))));
+
+    // identifier keys and shorthand properties are both plain identifiers in this grammar
+    let tree = PolyglotTree::from(
+        "const x = 1;\nPolyglot.export(\"point\", { x, y: \"up\" });",
+        util::Language::JavaScript,
+    )
+    .expect("This code parses");
+    tree.apply(&mut inferrer);
+    assert_eq!(
+        inferrer.get_type("point"),
+        Some(&Object(vec![
+            (String::from("x"), Number),
+            (String::from("y"), ValueType::String),
+        ]))
+    );
+}
+
+#[test]
+fn stub_generator_test() {
+    let file = PathBuf::from("TestSamples/model.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut generator = StubGenerator::new();
+    tree.apply(&mut generator);
+
+    let expected = std::fs::read_to_string("TestSamples/model_expected.d.ts")
+        .expect("Missing test file TestSamples/model_expected.d.ts");
+    assert_eq!(generator.get_typescript(), expected);
+    let expected = std::fs::read_to_string("TestSamples/model_expected.pyi")
+        .expect("Missing test file TestSamples/model_expected.pyi");
+    assert_eq!(generator.get_python(), expected);
+
+    // members that are not Python identifiers are left out of the stubs
+    let tree = PolyglotTree::from(
+        "Polyglot.export(\"point\", { x: 1, class: 2, $y: 3 });",
+        util::Language::JavaScript,
+    )
+    .expect("This code parses");
+    tree.apply(&mut generator);
+    assert!(generator
+        .get_python()
+        .contains("class _point(Protocol):\n    x: float\n\n"));
+    assert!(generator
+        .get_typescript()
+        .contains("x: number;\n        class: number;\n        $y: number;\n"));
+
+    // parameters that are reserved words in the other language are renamed
+    let tree = PolyglotTree::from(
+        "import polyglot\n\n@polyglot.export_value\ndef get(key, default=None, new=0):\n    return key\n",
+        util::Language::Python,
+    )
+    .expect("This code parses");
+    tree.apply(&mut generator);
+    assert!(generator
+        .get_typescript()
+        .contains("\"get\": (key: any, arg1?: any, arg2?: any) => any;"));
+    let tree = PolyglotTree::from(
+        "Polyglot.export(\"check\", function (from, is, value) { return value; });",
+        util::Language::JavaScript,
+    )
+    .expect("This code parses");
+    tree.apply(&mut generator);
+    assert!(generator
+        .get_python()
+        .contains("def __call__(self, arg0: Any, arg1: Any, value: Any) -> Any: ..."));
+
+    // paths mapping to the same class name get distinct classes
+    let tree = PolyglotTree::from(
+        "Polyglot.export(\"a-b\", { x: 1 });\nPolyglot.export(\"a_b\", { y: \"z\" });",
+        util::Language::JavaScript,
+    )
+    .expect("This code parses");
+    tree.apply(&mut generator);
+    let python = generator.get_python();
+    assert!(python.contains("class _a_b(Protocol):\n    x: float\n"));
+    assert!(python.contains("class _a_b_2(Protocol):\n    y: str\n"));
+    assert!(python.contains("def import_value(name: Literal[\"a_b\"]) -> _a_b_2: ..."));
+}
+
+#[test]
+fn interface_checker_test() {
+    let file = PathBuf::from("TestSamples/InterfaceTest.java");
+    let tree = PolyglotTree::from_path(file, util::Language::Java).expect("This test file exists");
+    let mut checker = InterfaceChecker::new();
+    tree.apply(&mut checker);
+
+    let missing: Vec<_> = checker
+        .get_missing_methods()
+        .iter()
+        .map(|m| {
+            let file = m.get_declaration().get_file().and_then(|f| f.file_name());
+            let row = m.get_cast().start_position().row;
+            (row, m.get_interface(), m.get_method(), file)
+        })
+        .collect();
+    let interface_test = Some(std::ffi::OsStr::new("InterfaceTest.java"));
+    let scorer = Some(std::ffi::OsStr::new("Scorer.java"));
+    assert_eq!(
+        missing,
+        [
+            (11, "Model", "name", interface_test),
+            (14, "Ranker", "rank", scorer),
+            (14, "Ranker", "size", scorer),
+        ]
+    );
+
+    // the imported com.acme.Ranker is checked rather than Ranker of the default package, with the methods it inherits
+    let file = PathBuf::from("TestSamples/QualifiedInterfaceTest.java");
+    let tree = PolyglotTree::from_path(file, util::Language::Java).expect("This test file exists");
+    tree.apply(&mut checker);
+    let missing: Vec<_> = checker
+        .get_missing_methods()
+        .iter()
+        .map(|m| {
+            let file = m.get_declaration().get_file().and_then(|f| f.file_name());
+            (m.get_interface(), m.get_method(), file)
+        })
+        .collect();
+    assert_eq!(
+        missing,
+        [("Ranker", "name", Some(std::ffi::OsStr::new("Named.java")))]
+    );
+}
+
+fn collect_host_type_lookups<'a>(
+    zip: Option<PolyglotZipper<'a>>,
+    lookups: &mut Vec<PolyglotZipper<'a>>,
+) {
+    if let Some(zip) = zip {
+        if zip.kind().eq("host_type_lookup") {
+            lookups.push(zip.clone());
+        }
+        collect_host_type_lookups(zip.child(0), lookups);
+        collect_host_type_lookups(zip.next_sibling(), lookups);
+    }
+}
+
+#[test]
+fn host_type_lookup_test() {
+    let file = PathBuf::from("TestSamples/host_lookup.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut lookups = Vec::new();
+    collect_host_type_lookups(Some(PolyglotZipper::from(&tree)), &mut lookups);
+
+    let lookups: Vec<_> = lookups
+        .iter()
+        .map(|z| {
+            let declaration = z.host_type_declaration().map(|d| {
+                let file = d.get_file().and_then(|f| f.file_name()?.to_str());
+                (d.kind().to_string(), file)
+            });
+            (z.get_host_type_name().unwrap(), declaration)
+        })
+        .collect();
+    let greeter = Some((String::from("class_declaration"), Some("Greeter.java")));
+    assert_eq!(
+        lookups,
+        [
+            (String::from("com.acme.Greeter"), greeter.clone()),
+            (String::from("java.util.ArrayList"), None),
+            (String::from("java.util.ArrayList"), None),
+            (String::from("com.acme.Greeter"), greeter),
+        ]
+    );
+}
+
+#[test]
+fn python_host_type_lookup_test() {
+    let code = "import java\nimport java as j\nimport java.util.ArrayList as List\nfrom java.util import HashMap as Map, Set\n\nj.type(\"com.acme.Greeter\")\nentry = java.util.Map.Entry\nqueue = j.util.ArrayDeque()\njava.util.Collections.sort(List())\n";
+    let tree = PolyglotTree::from(code, util::Language::Python).expect("The code is valid Python");
+    let mut lookups = Vec::new();
+    collect_host_type_lookups(Some(PolyglotZipper::from(&tree)), &mut lookups);
+
+    let lookups: Vec<_> = lookups
+        .iter()
+        .map(|z| (z.code().to_string(), z.get_host_type_name().unwrap()))
+        .collect();
+    let expected = [
+        ("java.util.ArrayList", "java.util.ArrayList"),
+        ("HashMap", "java.util.HashMap"),
+        ("Set", "java.util.Set"),
+        ("j.type(\"com.acme.Greeter\")", "com.acme.Greeter"),
+        ("java.util.Map", "java.util.Map"),
+        ("j.util.ArrayDeque", "java.util.ArrayDeque"),
+        ("java.util.Collections", "java.util.Collections"),
+    ];
+    assert_eq!(
+        lookups,
+        expected.map(|(code, name)| (String::from(code), String::from(name)))
+    );
+
+    // `java` is only a module of host classes when it is imported
+    let tree = PolyglotTree::from("java.util.ArrayList()", util::Language::Python)
+        .expect("The code is valid Python");
+    let mut lookups = Vec::new();
+    collect_host_type_lookups(Some(PolyglotZipper::from(&tree)), &mut lookups);
+    assert!(lookups.is_empty());
+}
+
+#[test]
+fn reflection_config_test() {
+    let file = PathBuf::from("TestSamples/host_lookup.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut builder = ReflectionConfigBuilder::new();
+    tree.apply(&mut builder);
+
+    let expected = std::fs::read_to_string("TestSamples/host_lookup_reflect-config.json")
+        .expect("Missing test file TestSamples/host_lookup_reflect-config.json");
+    assert_eq!(builder.get_config(), expected);
+}
+
+#[test]
+fn reflection_parameter_types_test() {
+    use std::sync::Arc;
+
+    let mut files = InMemoryResolver::new();
+    files.insert(
+        "app/main.js",
+        "const Builder = Java.type(\"com.acme.Builder\");\nconst b = new Builder();\nb.append(null);\nb.put(null);\nb.greet(null);\nb.fill(null, null);",
+    );
+    files.insert(
+        "app/com/acme/Builder.java",
+        "package com.acme;\n\nimport java.util.Map;\nimport java.util.concurrent.*;\n\npublic class Builder {\n    public void append(StringBuilder text) {}\n    public void put(Map.Entry<String, Integer> entry) {}\n    public void greet(Greeter greeter) {}\n    public void fill(ConcurrentMap<String, Exception> map, Thread[] threads) {}\n}",
+    );
+    files.insert(
+        "app/com/acme/Greeter.java",
+        "package com.acme;\n\npublic class Greeter {}",
+    );
+    let tree = PolyglotTree::from_path_with_resolver(
+        PathBuf::from("app/main.js"),
+        util::Language::JavaScript,
+        Arc::new(files),
+    )
+    .expect("main.js is in the resolver");
+    let mut builder = ReflectionConfigBuilder::new();
+    tree.apply(&mut builder);
+
+    let methods: Vec<_> = builder.get_classes()[0]
+        .get_methods()
+        .map(|m| (m.get_name(), m.get_parameter_types().join(", ")))
+        .collect();
+    assert_eq!(
+        methods,
+        [
+            ("<init>", String::new()),
+            ("append", String::from("java.lang.StringBuilder")),
+            (
+                "fill",
+                String::from("java.util.concurrent.ConcurrentMap, java.lang.Thread[]")
+            ),
+            ("greet", String::from("com.acme.Greeter")),
+            ("put", String::from("java.util.Map$Entry")),
+        ]
+    );
+}
+
+#[test]
+fn reflection_nested_class_test() {
+    use std::sync::Arc;
+
+    let mut files = InMemoryResolver::new();
+    files.insert(
+        "app/main.js",
+        "const Inner = Java.type(\"com.acme.Outer.Inner\");\nnew Inner();\nconst Outer = Java.type(\"com.acme.Outer\");",
+    );
+    files.insert(
+        "app/com/acme/Outer.java",
+        "package com.acme;\n\npublic class Outer {\n    public static class Inner {\n        public Inner() {}\n    }\n}",
+    );
+    let tree = PolyglotTree::from_path_with_resolver(
+        PathBuf::from("app/main.js"),
+        util::Language::JavaScript,
+        Arc::new(files),
+    )
+    .expect("main.js is in the resolver");
+    let mut builder = ReflectionConfigBuilder::new();
+    tree.apply(&mut builder);
+
+    let classes: Vec<_> = builder.get_classes().iter().map(|c| c.get_name()).collect();
+    assert_eq!(classes, ["com.acme.Outer", "com.acme.Outer$Inner"]);
+    let constructors: Vec<_> = builder.get_classes()[1]
+        .get_methods()
+        .map(|m| m.get_name())
+        .collect();
+    assert_eq!(constructors, ["<init>"]);
+}
+
+#[test]
+fn context_permission_test() {
+    use polyglot_tree::polyglot_processor::permissions::{Permission, PermissionIssueKind};
+
+    let file = PathBuf::from("TestSamples/ContextPermissions.java");
+    let tree = PolyglotTree::from_path(file, util::Language::Java).expect("This test file exists");
+    let mut checker = ContextPermissionChecker::new();
+    tree.apply(&mut checker);
+
+    let issues: Vec<_> = checker
+        .get_issues()
+        .iter()
+        .map(|i| {
+            let eval = i.get_eval().start_position().row;
+            (eval, i.get_kind().clone(), i.get_usage().code())
+        })
+        .collect();
+    let evalfile = "Polyglot.evalFile('python', 'export_x.py')";
+    assert_eq!(
+        issues,
+        [
+            (
+                5,
+                PermissionIssueKind::MissingPermission(Permission::HostClassLookup),
+                "Java.type('java.util.ArrayList')"
+            ),
+            (
+                6,
+                PermissionIssueKind::LanguageNotPermitted(String::from("python")),
+                "restricted.eval(\"python\", \"print(1)\")"
+            ),
+            (
+                10,
+                PermissionIssueKind::MissingPermission(Permission::PolyglotAccess),
+                evalfile
+            ),
+            (
+                10,
+                PermissionIssueKind::MissingPermission(Permission::IO),
+                evalfile
+            ),
+        ]
+    );
+}
+
+#[test]
+fn sandbox_linter_test() {
+    use polyglot_tree::polyglot_processor::sandbox::SandboxRule;
+
+    let file = PathBuf::from("TestSamples/sandbox.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut linter = SandboxLinter::new();
+    tree.apply(&mut linter);
+
+    let findings: Vec<_> = linter
+        .get_findings()
+        .iter()
+        .map(|f| {
+            let chain: Vec<_> = f
+                .get_chain()
+                .iter()
+                .map(|e| e.start_position().row)
+                .collect();
+            let host = f.get_host_file().and_then(|h| h.file_name()?.to_str());
+            (f.get_rule(), f.get_node().code(), chain, host)
+        })
+        .collect();
+    let host = Some("sandbox.js");
+    assert_eq!(
+        findings,
+        [
+            (
+                SandboxRule::Subprocess,
+                "subprocess.run(['ls'])",
+                vec![1],
+                host
+            ),
+            (SandboxRule::DynamicEval, "exec(code)", vec![1], host),
+            (
+                SandboxRule::ShellCommand,
+                "os.system(\"echo \" + command)",
+                vec![2],
+                host
+            ),
+            (
+                SandboxRule::RuntimeAccess,
+                "Java.type('java.lang.Runtime')",
+                vec![3, 0],
+                host
+            ),
+            (
+                SandboxRule::ChildProcess,
+                "require('child_process')",
+                vec![4, 0],
+                host
+            ),
+            // names imported with `from ... import` and `import ... as` are resolved to the module member
+            (SandboxRule::Subprocess, "run([\"ls\"])", vec![5], host),
+            (SandboxRule::ShellCommand, "o.system(\"ls\")", vec![5], host),
+            (SandboxRule::ShellCommand, "system(\"ls\")", vec![5], host),
+            (
+                SandboxRule::ShellCommand,
+                "open_pipe(\"ls\")",
+                vec![5],
+                host
+            ),
+        ]
+    );
+}
+
+#[test]
+fn dynamic_eval_reporter_test() {
+    use polyglot_tree::polyglot_processor::dynamic_eval::EvalArgument;
+
+    let file = PathBuf::from("TestSamples/dynamic_eval.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut reporter = DynamicEvalReporter::new();
+    tree.apply(&mut reporter);
+
+    let dynamic: Vec<_> = reporter
+        .get_dynamic_evals()
+        .iter()
+        .map(|d| {
+            (
+                d.get_eval().host_position(),
+                d.get_argument(),
+                d.get_expression().code(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        dynamic,
+        [
+            (
+                tree_sitter::Point::new(3, 11),
+                EvalArgument::Code,
+                "userInput"
+            ),
+            (
+                tree_sitter::Point::new(5, 0),
+                EvalArgument::Path,
+                "process.argv[2]"
+            ),
+            // the nested eval is located in the string literal of the host file
+            (tree_sitter::Point::new(6, 25), EvalArgument::Language, "l"),
+        ]
+    );
+
+    let coverage: Vec<_> = reporter
+        .get_coverage()
+        .iter()
+        .map(|c| {
+            (
+                c.get_file().is_some(),
+                c.get_resolved(),
+                c.get_unresolved(),
+                c.get_embedded(),
+            )
+        })
+        .collect();
+    assert_eq!(coverage, [(true, 2, 3, 1)]);
+    assert_eq!(
+        reporter.get_summary(),
+        "TestSamples/dynamic_eval.js: 2/5 eval calls resolved (40%)\n"
+    );
+}
+
+#[test]
+fn dynamic_eval_shared_file_test() {
+    let file = PathBuf::from("TestSamples/shared_eval.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let mut reporter = DynamicEvalReporter::new();
+    tree.apply(&mut reporter);
+
+    // shared_eval.py is evaluated by both shared_eval_a.js and shared_eval_b.js, but its calls are counted once
+    assert_eq!(reporter.get_dynamic_evals().len(), 1);
+    let coverage: Vec<_> = reporter
+        .get_coverage()
+        .iter()
+        .map(|c| {
+            (
+                c.get_file().and_then(|f| f.file_name()),
+                c.get_resolved(),
+                c.get_unresolved(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        coverage,
+        [
+            (Some(OsStr::new("shared_eval.js")), 2, 0),
+            (Some(OsStr::new("shared_eval.py")), 1, 1),
+            (Some(OsStr::new("shared_eval_a.js")), 1, 0),
+            (Some(OsStr::new("shared_eval_b.js")), 1, 0),
+        ]
+    );
+}
+
+#[test]
+fn host_position_test() {
+    let file = PathBuf::from("TestSamples/host_positions.js");
+    let host = std::fs::read_to_string(&file).expect("This test file exists");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let root = Some(PolyglotZipper::from(&tree));
+
+    let host_code = |code: &str| {
+        let node = find_node(root.clone(), code).expect("The node is in the test file");
+        (
+            &host[node.to_handle().host_byte_range()],
+            node.host_position(),
+            node.host_end_position(),
+        )
+    };
+    // top-level nodes keep their positions
+    assert_eq!(
+        host_code("x"),
+        (
+            "x",
+            tree_sitter::Point::new(0, 6),
+            tree_sitter::Point::new(0, 7)
+        )
+    );
+    // escaped quotes and line breaks of the JS literal are accounted for
+    assert_eq!(
+        host_code("b"),
+        (
+            "b",
+            tree_sitter::Point::new(1, 32),
+            tree_sitter::Point::new(1, 33)
+        )
+    );
+    assert_eq!(
+        host_code("\"two\""),
+        (
+            "\\\"two\\\"",
+            tree_sitter::Point::new(1, 36),
+            tree_sitter::Point::new(1, 43)
+        )
+    );
+    // escapes of both the JS and Python literals are accounted for in nested evals
+    let nested = find_node(root.clone(), "c").expect("The node is in the test file");
+    assert_eq!(nested.get_lang(), &util::Language::JavaScript);
+    assert!(nested
+        .get_file()
+        .is_some_and(|f| f.ends_with("host_positions.js")));
+    assert_eq!(&host[nested.host_byte_range()], "c");
+    assert_eq!(host_code("3").0, "\\\\x33");
+}
+
+#[test]
+fn node_at_test() {
+    use std::path::Path;
+    use util::Language::{JavaScript, Python};
+
+    let file = PathBuf::from("TestSamples/host_positions.js");
+    let tree = PolyglotTree::from_path(file, JavaScript).expect("This test file exists");
+    let path = Path::new("TestSamples/host_positions.js");
+
+    let nested = find_node(Some(PolyglotZipper::from(&tree)), "c").expect("c is in the test file");
+    let (node, languages) = tree
+        .node_at(path, nested.host_position())
+        .expect("The position is in the file");
+    assert!(node == nested);
+    assert_eq!(languages, [JavaScript, Python, JavaScript]);
+
+    let (node, languages) = tree
+        .node_at_byte(path, nested.host_byte_range().start)
+        .expect("The position is in the file");
+    assert!(node == nested);
+    assert_eq!(languages, [JavaScript, Python, JavaScript]);
+
+    // positions on the host code, or on the quotes of the literal, stay in the host
+    let (node, languages) = tree
+        .node_at(path, tree_sitter::Point::new(1, 2))
+        .expect("The position is in the file");
+    assert_eq!(node.code(), "Polyglot");
+    assert_eq!(languages, [JavaScript]);
+    let (node, _) = tree
+        .node_at(path, tree_sitter::Point::new(1, 24))
+        .expect("The position is in the file");
+    assert_eq!(node.kind(), "\"");
+
+    // files evaluated with evalFile are looked up by their own path
+    let tree = PolyglotTree::from_path(PathBuf::from("TestSamples/sandbox.js"), JavaScript)
+        .expect("This test file exists");
+    let (node, languages) = tree
+        .node_at(
+            Path::new("TestSamples/run_cmd.py"),
+            tree_sitter::Point::new(4, 1),
+        )
+        .expect("run_cmd.py is evaluated by sandbox.js");
+    assert_eq!(node.code(), "os");
+    assert_eq!(languages, [Python]);
+    assert!(tree
+        .node_at(
+            Path::new("TestSamples/missing.py"),
+            tree_sitter::Point::new(0, 0)
+        )
+        .is_none());
+}
+
+#[test]
+fn language_regions_test() {
+    use std::path::Path;
+    use util::Language::{JavaScript, Python};
+
+    let path = Path::new("TestSamples/host_positions.js");
+    let host = std::fs::read_to_string(path).expect("This test file exists");
+    let tree =
+        PolyglotTree::from_path(path.to_path_buf(), JavaScript).expect("This test file exists");
+
+    let regions: Vec<_> = tree
+        .language_regions(path)
+        .iter()
+        .map(|r| (&host[r.byte_range()], r.get_lang(), r.get_depth()))
+        .collect();
+    assert_eq!(
+        regions,
+        [
+            ("const x = 1;\nPolyglot.eval(\"python\", \"", JavaScript, 0),
+            (
+                "a = 1\\nb = \\\"two\\\"\\npolyglot.eval(language='js', string='",
+                Python,
+                1
+            ),
+            ("let c = \\\\x33", JavaScript, 2),
+            ("')", Python, 1),
+            ("\");\n", JavaScript, 0),
+        ]
+    );
+    assert!(tree
+        .language_regions(Path::new("TestSamples/missing.py"))
+        .is_empty());
+}
+
+#[test]
+fn position_encoding_test() {
+    use tree_sitter::Point;
+    use util::PositionEncoding::{CodePoints, Utf16, Utf8};
+
+    let file = PathBuf::from("TestSamples/unicode_positions.js");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::JavaScript).expect("This test file exists");
+    let x = find_node(Some(PolyglotZipper::from(&tree)), "x").expect("x is in the test file");
+
+    assert_eq!(x.start_position_in(Utf8), x.start_position());
+    assert_eq!(x.start_position_in(Utf8), Point::new(0, 17));
+    assert_eq!(x.start_position_in(Utf16), Point::new(0, 14));
+    assert_eq!(x.start_position_in(CodePoints), Point::new(0, 13));
+    assert_eq!(x.end_position_in(CodePoints), Point::new(0, 14));
+
+    assert_eq!(x.host_position_in(Utf8), x.host_position());
+    assert_eq!(x.host_position_in(Utf8), Point::new(0, 62));
+    assert_eq!(x.host_position_in(Utf16), Point::new(0, 57));
+    assert_eq!(x.host_position_in(CodePoints), Point::new(0, 56));
+    assert_eq!(x.host_end_position_in(Utf16), Point::new(0, 58));
+}
+
+#[test]
+fn source_encoding_test() {
+    use std::path::Path;
+
+    let path = Path::new("TestSamples/latin1.py");
+    let bytes = std::fs::read(path).expect("This test file exists");
+    let tree = PolyglotTree::from_path(path.to_path_buf(), util::Language::Python)
+        .expect("The coding cookie is detected");
+    let root = Some(PolyglotZipper::from(&tree));
+
+    let cafe = find_node(root.clone(), "café").expect("café is in the test file");
+    assert_eq!(&bytes[cafe.host_byte_range()], b"caf\xe9");
+    // the embedded JS code is mapped through both its literal and the transcoding
+    let cedilla = find_node(root.clone(), "let ç = 'é';").expect("The JS code is decoded");
+    let range = cedilla.host_byte_range();
+    assert_eq!(&bytes[range.clone()], b"let \xe7 = '\xe9';");
+    assert_eq!(cedilla.host_position(), tree_sitter::Point::new(3, 37));
+    assert_eq!(
+        cedilla.host_position_in(util::PositionEncoding::Utf16),
+        tree_sitter::Point::new(3, 37)
+    );
+    let (node, _) = tree
+        .node_at_byte(path, range.start + 4)
+        .expect("The position is in the file");
+    assert_eq!(node.code(), "ç");
+
+    let path = Path::new("TestSamples/utf16.js");
+    let bytes = std::fs::read(path).expect("This test file exists");
+    let tree = PolyglotTree::from_path(path.to_path_buf(), util::Language::JavaScript)
+        .expect("The byte order mark is detected");
+    let y = find_node(Some(PolyglotZipper::from(&tree)), "y").expect("y is in the test file");
+    assert_eq!(&bytes[y.host_byte_range()], b"y\x00");
+    assert_eq!(y.host_position(), tree_sitter::Point::new(1, 50));
+    assert_eq!(
+        y.host_position_in(util::PositionEncoding::Utf8),
+        tree_sitter::Point::new(1, 25)
+    );
+    assert_eq!(
+        y.host_position_in(util::PositionEncoding::CodePoints),
+        tree_sitter::Point::new(1, 25)
+    );
+
+    // an explicit encoding overrides the detected one
+    let tree = PolyglotTree::from_path_with_encoding(
+        PathBuf::from("TestSamples/latin1.py"),
+        util::Language::Python,
+        util::SourceEncoding::Utf8,
+    )
+    .expect("Invalid sequences are replaced");
+    assert!(find_node(Some(PolyglotZipper::from(&tree)), "café").is_none());
+}
+
+#[test]
+fn from_reader_test() {
+    let code = "import polyglot\npolyglot.eval(path=\"export_x.py\", language=\"python\")";
+    let tree = PolyglotTree::from_reader(
+        code.as_bytes(),
+        util::Language::Python,
+        PathBuf::from("<stdin>"),
+        PathBuf::from("TestSamples"),
+    )
+    .expect("Python is a supported language");
+    let root = PolyglotZipper::from(&tree);
+    assert_eq!(root.get_file(), Some(std::path::Path::new("<stdin>")));
+
+    // evalFile paths are resolved from the working directory
+    let eval = find_node(
+        Some(root),
+        "polyglot.eval(path=\"export_x.py\", language=\"python\")",
+    )
+    .and_then(|s| s.child(0))
+    .expect("The eval call is in the code");
+    assert!(eval.is_polyglot_eval_call());
+    let subtree = eval
+        .child(0)
+        .expect("export_x.py is in the working directory");
+    assert_eq!(
+        subtree.get_file(),
+        Some(std::path::Path::new("TestSamples/export_x.py"))
+    );
+}
+
+#[test]
+fn source_resolver_test() {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    let mut files = InMemoryResolver::new();
+    files.insert(
+        "app/main.js",
+        "Polyglot.evalFile('python', 'lib.py');\nPolyglot.evalFile('python', 'missing.py');",
+    );
+    files.insert(
+        "vendor/lib.py",
+        "import polyglot\npolyglot.eval(path='../app/util.js', language='js')",
+    );
+    files.insert("app/util.js", "const y = 42;");
+    let resolver = SearchPathResolver::with_resolver(vec![PathBuf::from("vendor")], files);
+
+    let tree = PolyglotTree::from_path_with_resolver(
+        PathBuf::from("app/main.js"),
+        util::Language::JavaScript,
+        Arc::new(resolver),
+    )
+    .expect("main.js is in the resolver");
+    let root = Some(PolyglotZipper::from(&tree));
+
+    // lib.py is found through the search path, and util.js relative to lib.py
+    let lib = find_node(root.clone(), "Polyglot.evalFile('python', 'lib.py')")
+        .and_then(|e| e.child(0))
+        .expect("lib.py is in the search path");
+    assert_eq!(lib.get_file(), Some(Path::new("vendor/lib.py")));
+    let y = find_node(root.clone(), "y").expect("util.js is resolved from lib.py");
+    assert_eq!(y.get_file(), Some(Path::new("app/util.js")));
+
+    let missing = find_node(root, "Polyglot.evalFile('python', 'missing.py')")
+        .expect("The eval call is in main.js");
+    assert!(missing.child(0).is_none());
+
+    assert!(PolyglotTree::from_path_with_resolver(
+        PathBuf::from("app/main.js"),
+        util::Language::JavaScript,
+        Arc::new(InMemoryResolver::new()),
+    )
+    .is_none());
+}
+
+#[test]
+fn resolver_host_class_test() {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    let mut files = InMemoryResolver::new();
+    files.insert(
+        "app/main.js",
+        "const Clock = Java.type(\"com.acme.Clock\");\nconst Other = Java.type(\"com.acme.Other\");",
+    );
+    files.insert(
+        "app/src/com/acme/Clock.java",
+        "package com.acme;\n\npublic class Clock {\n    public long now() { return 0; }\n}",
+    );
+    // Only the files of the project directory are host classes
+    files.insert(
+        "lib/com/acme/Other.java",
+        "package com.acme;\n\npublic class Other {}",
+    );
+
+    let tree = PolyglotTree::from_path_with_resolver(
+        PathBuf::from("app/main.js"),
+        util::Language::JavaScript,
+        Arc::new(files),
+    )
+    .expect("main.js is in the resolver");
+    let mut lookups = Vec::new();
+    collect_host_type_lookups(Some(PolyglotZipper::from(&tree)), &mut lookups);
+
+    let clock = lookups[0]
+        .host_type_declaration()
+        .expect("Clock.java is only in the resolver");
+    assert_eq!(clock.kind(), "class_declaration");
+    assert_eq!(
+        clock.get_file(),
+        Some(Path::new("app/src/com/acme/Clock.java"))
+    );
+    assert!(lookups[1].host_type_declaration().is_none());
+}
+
+#[test]
+fn cyclic_eval_test() {
+    let file = PathBuf::from("TestSamples/cycle_a.py");
+    let tree =
+        PolyglotTree::from_path(file, util::Language::Python).expect("This test file exists");
+
+    let mut references = Vec::new();
+    let mut stack = vec![PolyglotZipper::from(&tree)];
+    while let Some(zip) = stack.pop() {
+        if zip.is_cyclic_eval_reference() {
+            let target = zip
+                .get_cyclic_reference()
+                .expect("This is a cyclic reference");
+            references.push((
+                zip.kind().to_string(),
+                zip.code().to_string(),
+                target.to_path_buf(),
+            ));
+        }
+        stack.extend(zip.child(0));
+        stack.extend(zip.next_sibling());
+    }
+    // references point at the already open ancestor, whatever the path they were evaluated with
+    let canonical = |path: &str| std::fs::canonicalize(path).expect("This test file exists");
+    // b.js evaluating a.py, then b.js evaluating itself
+    assert_eq!(
+        references,
+        [
+            (
+                String::from("cyclic_eval_reference"),
+                String::from("Polyglot.evalFile(\"js\", \"cycle_b.js\")"),
+                canonical("TestSamples/cycle_b.js")
+            ),
+            (
+                String::from("cyclic_eval_reference"),
+                String::from("Polyglot.evalFile(\"python\", \"./cycle_a.py\")"),
+                canonical("TestSamples/cycle_a.py")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn shared_subtree_test() {
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    /// A resolver recording the files it reads.
+    struct RecordingResolver {
+        files: InMemoryResolver,
+        reads: Mutex<Vec<PathBuf>>,
+    }
+
+    impl SourceResolver for RecordingResolver {
+        fn resolve(&self, working_dir: &Path, path: &Path) -> Option<(PathBuf, Vec<u8>)> {
+            let resolved = self.files.resolve(working_dir, path)?;
+            self.reads.lock().unwrap().push(resolved.0.clone());
+            Some(resolved)
+        }
+    }
+
+    let mut files = InMemoryResolver::new();
+    files.insert(
+        "main.js",
+        "Polyglot.evalFile('python', 'utils.py');\nPolyglot.evalFile(\"python\", \"utils.py\");\nPolyglot.evalFile('js', 'lib/other.js');",
+    );
+    files.insert(
+        "lib/other.js",
+        "Polyglot.evalFile('python', '../utils.py');",
+    );
+    files.insert("utils.py", "x = 42");
+    let resolver = Arc::new(RecordingResolver {
+        files,
+        reads: Mutex::new(Vec::new()),
+    });
+
+    let tree = PolyglotTree::from_path_with_resolver(
+        PathBuf::from("main.js"),
+        util::Language::JavaScript,
+        resolver.clone(),
+    )
+    .expect("main.js is in the resolver");
+    let root = PolyglotZipper::from(&tree);
+
+    let subtrees: Vec<PolyglotZipper> = [
+        "Polyglot.evalFile('python', 'utils.py')",
+        "Polyglot.evalFile(\"python\", \"utils.py\")",
+        "Polyglot.evalFile('python', '../utils.py')",
+    ]
+    .iter()
+    .map(|call| {
+        find_node(Some(root.clone()), call)
+            .and_then(|e| e.child(0))
+            .expect("utils.py is in the resolver")
+    })
+    .collect();
+
+    // utils.py is only built once, whether it is evaluated from main.js or from lib/other.js
+    assert!(subtrees[0].same_tree_as(&subtrees[1]));
+    assert!(subtrees[0].same_tree_as(&subtrees[2]));
+    assert!(!subtrees[0].same_tree_as(&root));
+    assert_eq!(subtrees[2].code(), "x = 42");
+    // and only read once
+    assert_eq!(
+        *resolver.reads.lock().unwrap(),
+        [
+            PathBuf::from("main.js"),
+            PathBuf::from("utils.py"),
+            PathBuf::from("lib/other.js")
+        ]
+    );
+}
+
+#[test]
+fn parallel_build_test() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<PolyglotTree>();
+
+    for (file, lang) in [
+        (
+            "TestSamples/test_pyprint_file.js",
+            util::Language::JavaScript,
+        ),
+        ("TestSamples/dynamic_eval.js", util::Language::JavaScript),
+        ("TestSamples/JavaTest.java", util::Language::Java),
+        ("TestSamples/cycle_a.py", util::Language::Python),
+    ] {
+        let sequential =
+            PolyglotTree::from_path(PathBuf::from(file), lang).expect("This test file exists");
+        let parallel = PolyglotTree::from_path_parallel(PathBuf::from(file), lang, 4)
+            .expect("This test file exists");
+
+        let mut expected = TreePrinter::new();
+        sequential.apply(&mut expected);
+        let mut actual = TreePrinter::new();
+        parallel.apply(&mut actual);
+        assert_eq!(expected.get_result(), actual.get_result(), "{file}");
+    }
+}
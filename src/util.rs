@@ -5,6 +5,7 @@ use thiserror::Error;
 pub struct InvalidArgumentError;
 
 /// An enumeration that represents all languages supported by this crate. Current options are Python, JavaScript and Java.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Language {
     Python,
     JavaScript,
@@ -12,6 +13,101 @@ pub enum Language {
     Java,
 }
 
+/// The units in which the columns of positions are counted.
+///
+/// tree-sitter counts columns in UTF-8 bytes, while editors commonly count them in UTF-16 code units (such as LSP clients by default)
+/// or in Unicode code points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PositionEncoding {
+    #[default]
+    Utf8,
+    Utf16,
+    CodePoints,
+}
+
+impl PositionEncoding {
+    /// Returns the length of the given text in units of this encoding.
+    ///
+    /// # Examples
+    /// ```
+    /// use polyglot_ast::util::PositionEncoding;
+    ///
+    /// assert_eq!(PositionEncoding::Utf8.measure("é😀"), 6);
+    /// assert_eq!(PositionEncoding::Utf16.measure("é😀"), 3);
+    /// assert_eq!(PositionEncoding::CodePoints.measure("é😀"), 2);
+    /// ```
+    pub fn measure(&self, text: &str) -> usize {
+        match self {
+            PositionEncoding::Utf8 => text.len(),
+            PositionEncoding::Utf16 => text.encode_utf16().count(),
+            PositionEncoding::CodePoints => text.chars().count(),
+        }
+    }
+}
+
+/// The character encodings source files can be read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, in which each byte is the code point of the same value.
+    Latin1,
+}
+
+/// Returns the encoding source code of the given language is most likely written in.
+///
+/// The encoding is read from the byte order mark if there is one, then for Python from a PEP 263 coding cookie
+/// in the first two lines (of the UTF-8 or Latin-1 families; others are ignored).
+/// Otherwise, the code is UTF-8 if it is valid UTF-8, and Latin-1 if it is not.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util::{self, Language, SourceEncoding};
+///
+/// assert_eq!(util::detect_source_encoding(b"\xff\xfex\x00", &Language::Java), SourceEncoding::Utf16Le);
+/// assert_eq!(util::detect_source_encoding(b"# -*- coding: latin-1 -*-\nx = 1", &Language::Python), SourceEncoding::Latin1);
+/// assert_eq!(util::detect_source_encoding("x = 'é'".as_bytes(), &Language::Python), SourceEncoding::Utf8);
+/// assert_eq!(util::detect_source_encoding(b"x = '\xe9'", &Language::Python), SourceEncoding::Latin1);
+/// ```
+pub fn detect_source_encoding(bytes: &[u8], language: &Language) -> SourceEncoding {
+    if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+        return SourceEncoding::Utf8;
+    } else if bytes.starts_with(&[0xff, 0xfe]) {
+        return SourceEncoding::Utf16Le;
+    } else if bytes.starts_with(&[0xfe, 0xff]) {
+        return SourceEncoding::Utf16Be;
+    }
+
+    if language == &Language::Python {
+        let cookie = bytes
+            .split(|b| *b == b'\n')
+            .take(2)
+            .map(String::from_utf8_lossy)
+            .take_while(|l| l.trim_start().starts_with('#') || l.trim().is_empty())
+            .find_map(|l| {
+                let start = l.find("coding")? + "coding".len();
+                let name = l[start..].strip_prefix([':', '='])?.trim_start();
+                let end = name
+                    .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                    .unwrap_or(name.len());
+                Some(name[..end].to_lowercase().replace('_', "-"))
+            });
+        match cookie.as_deref() {
+            Some("utf-8" | "utf8" | "utf-8-sig") => return SourceEncoding::Utf8,
+            Some(
+                "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" | "iso-latin-1" | "l1" | "cp819",
+            ) => return SourceEncoding::Latin1,
+            _ => {}
+        }
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => SourceEncoding::Utf8,
+        Err(_) => SourceEncoding::Latin1,
+    }
+}
+
 /// Returns a String identical to the provided slice but with leading and trailing characters removed.
 /// In practice, this is mostly used to remove quotes from string literals, but the function does not actually check which characters it removes.
 ///
@@ -114,3 +210,183 @@ pub fn language_string_to_enum(lang: &str) -> Result<Language, InvalidArgumentEr
         _ => Err(InvalidArgumentError),
     }
 }
+
+/// Returns the GraalVM identifier of the Language enum reference passed, as used by polyglot eval calls.
+///
+/// # Example
+/// ```
+/// use polyglot_ast::util;
+/// use util::Language;
+///
+/// assert_eq!(util::language_enum_to_string(&Language::JavaScript), "js");
+/// ```
+pub fn language_enum_to_string(lang: &Language) -> &'static str {
+    match lang {
+        Language::Python => "python",
+        Language::JavaScript => "js",
+        Language::Java => "java",
+    }
+}
+
+/// Returns the value denoted by a string literal, with its prefix and quotes removed and its escape sequences decoded.
+///
+/// This understands the literal syntaxes of every supported language: Python prefixes (`r`, `b`, `u`, `f`) and triple quotes,
+/// JavaScript template strings and Java string literals.
+/// Raw Python strings are returned as-is, and unknown escape sequences are kept verbatim, backslash included.
+/// The function does not check that interpolations (`f"{x}"`, `` `${x}` ``) are absent; callers should do so beforehand.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util;
+///
+/// assert_eq!(util::unescape_string_literal("\"x\\n\\x41\""), String::from("x\nA"));
+/// assert_eq!(util::unescape_string_literal("r'x\\n'"), String::from("x\\n"));
+/// assert_eq!(util::unescape_string_literal("\"\"\"doc\"\"\""), String::from("doc"));
+/// assert_eq!(util::unescape_string_literal("`\\u{41}`"), String::from("A"));
+/// ```
+pub fn unescape_string_literal(s: &str) -> String {
+    unescape_string_literal_mapped(s).0
+}
+
+/// Internal variant of `unescape_string_literal` that also maps the decoded value back onto the literal.
+///
+/// Along with the value, returns pairs of (offset in the value, offset in the literal) for each place the two are shifted apart,
+/// which are the start of the value and the end of each escape sequence, in increasing order.
+/// An offset in the value maps to the literal through the last pair starting at or before it.
+pub(crate) fn unescape_string_literal_mapped(s: &str) -> (String, Vec<(usize, usize)>) {
+    let prefix_len = s.find(['"', '\'', '`']).unwrap_or(0);
+    let raw = s[..prefix_len].contains(['r', 'R']);
+    let body = &s[prefix_len..];
+    let quote_len = if body.starts_with("\"\"\"") || body.starts_with("'''") {
+        3
+    } else {
+        1
+    };
+    if body.len() < 2 * quote_len {
+        return (String::new(), vec![(0, s.len())]);
+    }
+    let start = prefix_len + quote_len;
+    let body = &body[quote_len..body.len() - quote_len];
+    let mut segments = vec![(0, start)];
+    if raw {
+        return (String::from(body), segments);
+    }
+
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let escaped = match chars.next().map(|(_, e)| e) {
+            Some(e) => e,
+            None => {
+                result.push(c);
+                break;
+            }
+        };
+        match escaped {
+            'n' => result.push('\n'),
+            't' => result.push('\t'),
+            'r' => result.push('\r'),
+            'a' => result.push('\x07'),
+            'b' => result.push('\x08'),
+            'f' => result.push('\x0c'),
+            'v' => result.push('\x0b'),
+            '\\' | '\'' | '"' | '`' | '$' => result.push(escaped),
+            '\n' => {} // line continuation
+            '\r' => {
+                chars.next_if(|(_, c)| *c == '\n');
+            }
+            'x' | 'u' | 'U' => {
+                let digits: String =
+                    if escaped == 'u' && chars.next_if(|(_, c)| *c == '{').is_some() {
+                        let digits = chars
+                            .by_ref()
+                            .map(|(_, c)| c)
+                            .take_while(|c| *c != '}')
+                            .collect();
+                        digits
+                    } else {
+                        let len = match escaped {
+                            'x' => 2,
+                            'u' => 4,
+                            _ => 8,
+                        };
+                        let mut digits = String::new();
+                        while digits.len() < len {
+                            match chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                                Some((_, d)) => digits.push(d),
+                                None => break,
+                            }
+                        }
+                        digits
+                    };
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(decoded) => result.push(decoded),
+                    None => {
+                        result.push(c);
+                        result.push(escaped);
+                        result.push_str(&digits);
+                    }
+                }
+            }
+            '0'..='7' => {
+                let mut value = escaped.to_digit(8).unwrap_or_default();
+                for _ in 0..2 {
+                    match chars.next_if(|(_, c)| c.is_digit(8)) {
+                        Some((_, d)) => value = value * 8 + d.to_digit(8).unwrap_or_default(),
+                        None => break,
+                    }
+                }
+                result.push(char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            other => {
+                result.push(c);
+                result.push(other);
+            }
+        }
+        // the escape sequence ends where the next unread character starts
+        let end = chars.peek().map_or(body.len(), |(i, _)| *i);
+        segments.push((result.len(), start + end));
+    }
+    (result, segments)
+}
+
+/// Returns true if the text matches the pattern, in which `*` matches any sequence of characters, including an empty one.
+///
+/// # Examples
+/// ```
+/// use polyglot_ast::util;
+///
+/// assert!(util::glob_match("os.system", "os.system"));
+/// assert!(util::glob_match("*.exec", "require('child_process').exec"));
+/// assert!(util::glob_match("req.*", "req.body"));
+/// assert!(!util::glob_match("req.*", "request.body"));
+/// ```
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match text.strip_prefix(first) {
+        Some(r) => r,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let last = match parts.split_last() {
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    None => return false,
+                }
+            }
+            last
+        }
+        None => return rest.is_empty(), // no wildcard at all
+    };
+    rest.ends_with(last)
+}